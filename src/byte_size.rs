@@ -0,0 +1,249 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::interpolation::has_variables;
+
+/// A size in bytes such as `shm_size` or `mem_limit`, given either as an
+/// integer or as a string with a unit.
+///
+/// Units are `b`, `k`, `m`, `g`, `t` and `p`, optionally followed by `b` (or
+/// `ib`), case-insensitive and in multiples of 1024. The number may have a
+/// fractional part, as in `0.5g`. The original form is kept so values
+/// serialize back the way they were written; comparison only looks at the
+/// number of bytes.
+///
+/// Strings referencing variables, such as `${MEM:-512m}`, are kept as they are
+/// until they are interpolated.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "RawByteSize", into = "RawByteSize")]
+pub struct ByteSize {
+    /// `None` while `text` references variables
+    bytes: Option<u64>,
+    /// The string this size was parsed from, `None` for plain integers
+    text: Option<String>,
+}
+
+impl ByteSize {
+    pub fn new(bytes: u64) -> Self {
+        Self {
+            bytes: Some(bytes),
+            text: None,
+        }
+    }
+
+    /// Returns the number of bytes, `None` if the size references variables
+    /// that have not been interpolated
+    pub fn as_bytes(&self) -> Option<u64> {
+        self.bytes
+    }
+}
+
+impl Default for ByteSize {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl PartialEq for ByteSize {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.bytes, other.bytes) {
+            (None, None) => self.text == other.text,
+            (bytes, other_bytes) => bytes == other_bytes,
+        }
+    }
+}
+
+impl Eq for ByteSize {}
+
+impl Hash for ByteSize {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self.bytes {
+            Some(bytes) => bytes.hash(state),
+            None => self.text.hash(state),
+        }
+    }
+}
+
+impl From<u64> for ByteSize {
+    fn from(bytes: u64) -> Self {
+        Self::new(bytes)
+    }
+}
+
+impl FromStr for ByteSize {
+    type Err = ByteSizeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = if has_variables(s) {
+            None
+        } else {
+            Some(parse(s).ok_or_else(|| ByteSizeParseError(s.to_string()))?)
+        };
+        Ok(Self {
+            bytes,
+            text: Some(s.to_string()),
+        })
+    }
+}
+
+impl TryFrom<&str> for ByteSize {
+    type Error = ByteSizeParseError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl TryFrom<String> for ByteSize {
+    type Error = ByteSizeParseError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.text {
+            Some(text) => write!(f, "{text}"),
+            None => write!(f, "{}", self.bytes.unwrap_or_default()),
+        }
+    }
+}
+
+/// The result of a failed conversion to [`ByteSize`]
+///
+/// Contains the string that was being converted
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ByteSizeParseError(pub String);
+
+impl fmt::Display for ByteSizeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid size {:?}, expected a number of bytes optionally followed by a unit such as 'k', 'm' or 'gb'",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ByteSizeParseError {}
+
+/// The serialized forms of a [`ByteSize`]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum RawByteSize {
+    Bytes(u64),
+    Text(String),
+}
+
+impl TryFrom<RawByteSize> for ByteSize {
+    type Error = ByteSizeParseError;
+
+    fn try_from(raw: RawByteSize) -> Result<Self, Self::Error> {
+        match raw {
+            RawByteSize::Bytes(bytes) => Ok(Self::new(bytes)),
+            RawByteSize::Text(text) => text.parse(),
+        }
+    }
+}
+
+impl From<ByteSize> for RawByteSize {
+    fn from(size: ByteSize) -> Self {
+        match size.text {
+            Some(text) => RawByteSize::Text(text),
+            None => RawByteSize::Bytes(size.bytes.unwrap_or_default()),
+        }
+    }
+}
+
+fn parse(s: &str) -> Option<u64> {
+    let s = s.trim();
+    let number_len = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(number_len);
+    if number.is_empty() || number.starts_with('.') || number.ends_with('.') {
+        return None;
+    }
+
+    let unit = unit.trim_start().to_ascii_lowercase();
+    let prefix = unit
+        .strip_suffix("ib")
+        .or_else(|| unit.strip_suffix('b'))
+        .unwrap_or(&unit);
+    let multiplier: u64 = match prefix {
+        "" if !unit.ends_with("ib") => 1,
+        "k" => 1 << 10,
+        "m" => 1 << 20,
+        "g" => 1 << 30,
+        "t" => 1 << 40,
+        "p" => 1 << 50,
+        _ => return None,
+    };
+
+    match number.split_once('.') {
+        None => number.parse::<u64>().ok()?.checked_mul(multiplier),
+        Some((whole, fraction)) => {
+            let whole = whole.parse::<u64>().ok()?.checked_mul(multiplier)?;
+            let fraction: f64 = format!("0.{fraction}").parse().ok()?;
+            whole.checked_add((fraction * multiplier as f64) as u64)
+        }
+    }
+}
+
+/// The `memswap_limit` of a service: the amount of memory plus swap it may
+/// use, or `-1` for unlimited swap
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
+#[serde(try_from = "RawMemswapLimit", into = "RawMemswapLimit")]
+pub enum MemswapLimit {
+    Unlimited,
+    Limited(ByteSize),
+}
+
+impl fmt::Display for MemswapLimit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Unlimited => write!(f, "-1"),
+            Self::Limited(size) => write!(f, "{size}"),
+        }
+    }
+}
+
+/// The serialized forms of a [`MemswapLimit`]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum RawMemswapLimit {
+    Signed(i64),
+    Size(RawByteSize),
+}
+
+impl TryFrom<RawMemswapLimit> for MemswapLimit {
+    type Error = ByteSizeParseError;
+
+    fn try_from(raw: RawMemswapLimit) -> Result<Self, Self::Error> {
+        match raw {
+            RawMemswapLimit::Signed(-1) => Ok(Self::Unlimited),
+            RawMemswapLimit::Signed(bytes) => u64::try_from(bytes)
+                .map(|bytes| Self::Limited(ByteSize::new(bytes)))
+                .map_err(|_| ByteSizeParseError(bytes.to_string())),
+            RawMemswapLimit::Size(RawByteSize::Text(text)) if text.trim() == "-1" => {
+                Ok(Self::Unlimited)
+            }
+            RawMemswapLimit::Size(size) => size.try_into().map(Self::Limited),
+        }
+    }
+}
+
+impl From<MemswapLimit> for RawMemswapLimit {
+    fn from(limit: MemswapLimit) -> Self {
+        match limit {
+            MemswapLimit::Unlimited => RawMemswapLimit::Signed(-1),
+            MemswapLimit::Limited(size) => RawMemswapLimit::Size(size.into()),
+        }
+    }
+}
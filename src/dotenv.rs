@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use crate::interpolation::{interpolate, VariableSource};
+
+/// The variables defined in a `.env` file, in the order they appear.
+///
+/// Parsing follows docker compose: `export` prefixes are ignored, values can be
+/// unquoted, single-quoted (taken literally) or double-quoted (supporting
+/// escape sequences and spanning several lines), `#` starts a comment in
+/// unquoted values when preceded by whitespace, and unquoted and double-quoted
+/// values can reference variables defined earlier in the file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Dotenv {
+    /// Variables in file order. A bare `VAR` line has no value, it is up to
+    /// the caller to look it up.
+    pub vars: Vec<(String, Option<String>)>,
+    /// Lines that could not be parsed, parsing continues after each error
+    pub errors: Vec<DotenvError>,
+}
+
+/// A line of a `.env` file that could not be parsed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DotenvError {
+    /// 1-based line number the failing entry starts on
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for DotenvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for DotenvError {}
+
+impl Dotenv {
+    /// Parses `input` without resolving references to undefined variables
+    pub fn parse(input: &str) -> Self {
+        Self::parse_with(input, &HashMap::new())
+    }
+
+    /// Parses `input`, using `lookup` for references, which takes precedence
+    /// over variables defined in the file. Bare `VAR` entries are left without
+    /// a value.
+    pub fn parse_with<V>(input: &str, lookup: &V) -> Self
+    where
+        V: VariableSource + ?Sized,
+    {
+        let mut vars = Vec::new();
+        let mut errors = Vec::new();
+        let mut defined = HashMap::new();
+        let mut lines = input.lines().enumerate();
+
+        while let Some((index, line)) = lines.next() {
+            let line_number = index + 1;
+            let line = line.trim_start();
+            if line.trim_end().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line = line.strip_prefix("export ").map_or(line, str::trim_start);
+            let mut error = |message: &str| {
+                errors.push(DotenvError {
+                    line: line_number,
+                    message: message.to_string(),
+                })
+            };
+
+            let (key, raw) = match line.split_once('=') {
+                Some((key, raw)) => (key.trim_end(), Some(raw.trim_start())),
+                None => (line.trim_end(), None),
+            };
+            if !is_valid_key(key) {
+                error(&format!("invalid variable name {key:?}"));
+                continue;
+            }
+
+            let Some(raw) = raw else {
+                vars.push((key.to_string(), None));
+                continue;
+            };
+
+            let value = match raw.chars().next() {
+                Some(quote @ ('\'' | '"')) => {
+                    let mut text = raw[1..].to_string();
+                    let end = loop {
+                        if let Some(end) = closing_quote(&text, quote) {
+                            break Some(end);
+                        }
+                        match lines.next() {
+                            Some((_, next)) => {
+                                text.push('\n');
+                                text.push_str(next);
+                            }
+                            None => break None,
+                        }
+                    };
+                    let Some(end) = end else {
+                        error("unterminated quoted value");
+                        continue;
+                    };
+                    let trailing = text[end + 1..].trim_start();
+                    if !trailing.is_empty() && !trailing.starts_with('#') {
+                        error("unexpected characters after quoted value");
+                        continue;
+                    }
+                    let value = &text[..end];
+                    if quote == '\'' {
+                        Ok(value.to_string())
+                    } else {
+                        expand(&unescape(value), lookup, &defined)
+                    }
+                }
+                _ => {
+                    let value = match raw.find(" #").or_else(|| raw.find("\t#")) {
+                        Some(comment) => &raw[..comment],
+                        None => raw,
+                    };
+                    expand(value.trim_end(), lookup, &defined)
+                }
+            };
+
+            match value {
+                Ok(value) => {
+                    defined.insert(key.to_string(), value.clone());
+                    vars.push((key.to_string(), Some(value)));
+                }
+                Err(message) => error(&message),
+            }
+        }
+        Dotenv { vars, errors }
+    }
+
+    /// Reads and parses the file at `path`, see [`parse_with`](Self::parse_with)
+    pub fn read<V>(path: impl AsRef<Path>, lookup: &V) -> std::io::Result<Self>
+    where
+        V: VariableSource + ?Sized,
+    {
+        let content = std::fs::read_to_string(path)?;
+        Ok(Self::parse_with(&content, lookup))
+    }
+
+    /// Returns the last value defined for `name`
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.vars
+            .iter()
+            .rev()
+            .find(|(key, _)| key == name)
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vars.is_empty()
+    }
+}
+
+impl VariableSource for Dotenv {
+    fn var(&self, name: &str) -> Option<String> {
+        self.get(name).map(str::to_string)
+    }
+}
+
+fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+/// Byte offset of the quote closing a value that started with `quote`
+fn closing_quote(text: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            '\\' if quote == '"' && !escaped => escaped = true,
+            c if c == quote && !escaped => return Some(i),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+/// Resolves escape sequences of a double-quoted value. An escaped `$` is kept
+/// as `$$` so it survives interpolation.
+fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('t') => result.push('\t'),
+            Some('$') => result.push_str("$$"),
+            Some(other @ ('\\' | '"')) => result.push(other),
+            Some(other) => {
+                result.push('\\');
+                result.push(other);
+            }
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+fn expand<V>(value: &str, lookup: &V, defined: &HashMap<String, String>) -> Result<String, String>
+where
+    V: VariableSource + ?Sized,
+{
+    interpolate(value, &(lookup, defined)).map_err(|e| e.to_string())
+}
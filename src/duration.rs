@@ -0,0 +1,206 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::interpolation::has_variables;
+
+/// A duration such as `1m30s`, `500ms` or `1.5h`, as used by
+/// `stop_grace_period`, healthchecks and deploy policies.
+///
+/// Values are parsed like Go's `time.ParseDuration`: a sequence of decimal
+/// numbers, each with a unit out of `ns`, `us` (or `µs`), `ms`, `s`, `m` and
+/// `h`. They are serialized in a compact canonical form listing the non-zero
+/// units, e.g. `90s` becomes `1m30s` and `1.5s` becomes `1s500ms`.
+///
+/// Strings referencing variables, such as `${GRACE:-10s}`, are kept as they
+/// are until they are interpolated.
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
+#[serde(try_from = "String", into = "String")]
+pub struct ComposeDuration(Inner);
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+enum Inner {
+    Parsed(Duration),
+    /// A string referencing variables
+    Unresolved(String),
+}
+
+impl ComposeDuration {
+    /// Returns the duration, `None` if it references variables that have not
+    /// been interpolated
+    pub fn as_duration(&self) -> Option<Duration> {
+        match &self.0 {
+            Inner::Parsed(duration) => Some(*duration),
+            Inner::Unresolved(_) => None,
+        }
+    }
+}
+
+impl Default for ComposeDuration {
+    fn default() -> Self {
+        Self(Inner::Parsed(Duration::ZERO))
+    }
+}
+
+impl From<Duration> for ComposeDuration {
+    fn from(duration: Duration) -> Self {
+        Self(Inner::Parsed(duration))
+    }
+}
+
+impl From<ComposeDuration> for String {
+    fn from(duration: ComposeDuration) -> Self {
+        duration.to_string()
+    }
+}
+
+impl FromStr for ComposeDuration {
+    type Err = DurationParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if has_variables(s) {
+            return Ok(Self(Inner::Unresolved(s.to_string())));
+        }
+        parse(s)
+            .map(Self::from)
+            .map_err(|message| DurationParseError {
+                value: s.to_string(),
+                message,
+            })
+    }
+}
+
+impl TryFrom<String> for ComposeDuration {
+    type Error = DurationParseError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl TryFrom<&str> for ComposeDuration {
+    type Error = DurationParseError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for ComposeDuration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let duration = match &self.0 {
+            Inner::Parsed(duration) => duration,
+            Inner::Unresolved(text) => return f.write_str(text),
+        };
+        if duration.is_zero() {
+            return write!(f, "0s");
+        }
+        let secs = duration.as_secs();
+        let nanos = duration.subsec_nanos();
+        let units = [
+            (secs / 3600, "h"),
+            (secs / 60 % 60, "m"),
+            (secs % 60, "s"),
+            (u64::from(nanos / 1_000_000), "ms"),
+            (u64::from(nanos / 1_000 % 1_000), "us"),
+            (u64::from(nanos % 1_000), "ns"),
+        ];
+        for (value, unit) in units {
+            if value != 0 {
+                write!(f, "{value}{unit}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The result of a failed conversion to [`ComposeDuration`]
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct DurationParseError {
+    pub value: String,
+    pub message: &'static str,
+}
+
+impl fmt::Display for DurationParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid duration {:?}: {}", self.value, self.message)
+    }
+}
+
+impl std::error::Error for DurationParseError {}
+
+fn parse(s: &str) -> Result<Duration, &'static str> {
+    let s = s.strip_prefix('+').unwrap_or(s);
+    if s.starts_with('-') {
+        return Err("negative durations are not supported");
+    }
+    if s == "0" {
+        return Ok(Duration::ZERO);
+    }
+    if s.is_empty() {
+        return Err("empty duration");
+    }
+
+    let mut total: u128 = 0;
+    let mut rest = s;
+    while !rest.is_empty() {
+        let whole_len = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let (whole, after) = rest.split_at(whole_len);
+        let (fraction, after) = match after.strip_prefix('.') {
+            Some(after) => {
+                let len = after
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(after.len());
+                after.split_at(len)
+            }
+            None => ("", after),
+        };
+        if whole.is_empty() && fraction.is_empty() {
+            return Err("expected a number");
+        }
+
+        let unit_len = after
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(after.len());
+        let (unit, after) = after.split_at(unit_len);
+        let nanos_per_unit: u128 = match unit {
+            "ns" => 1,
+            "us" | "µs" | "μs" => 1_000,
+            "ms" => 1_000_000,
+            "s" => 1_000_000_000,
+            "m" => 60 * 1_000_000_000,
+            "h" => 3600 * 1_000_000_000,
+            "" => return Err("missing unit"),
+            _ => return Err("unknown unit"),
+        };
+
+        let overflow = "duration is too large";
+        let whole: u128 = match whole {
+            "" => 0,
+            whole => whole.parse().map_err(|_| overflow)?,
+        };
+        // Digits beyond nanosecond precision of an hour don't matter
+        let fraction = &fraction[..fraction.len().min(18)];
+        let fraction_nanos = match fraction {
+            "" => 0,
+            fraction => {
+                let digits: u128 = fraction.parse().map_err(|_| overflow)?;
+                digits * nanos_per_unit / 10u128.pow(fraction.len() as u32)
+            }
+        };
+        total = whole
+            .checked_mul(nanos_per_unit)
+            .and_then(|nanos| nanos.checked_add(fraction_nanos))
+            .and_then(|nanos| total.checked_add(nanos))
+            .ok_or(overflow)?;
+        rest = after;
+    }
+
+    let secs = u64::try_from(total / 1_000_000_000).map_err(|_| "duration is too large")?;
+    Ok(Duration::new(secs, (total % 1_000_000_000) as u32))
+}
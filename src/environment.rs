@@ -0,0 +1,272 @@
+use std::path::PathBuf;
+
+use crate::interpolation::VariableSource;
+use crate::loader::{LoadError, Project};
+use crate::{Dotenv, Environment, Map, Service, SingleValue, StringOrList};
+
+/// The variables of an [`Environment`] in declaration order, regardless of
+/// whether they were written as a list or a mapping.
+///
+/// A variable without a value (`KEY` in a list, `KEY:` in a mapping) is
+/// inherited from the host and is different from an empty value (`KEY=` or
+/// `KEY: ""`).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EnvironmentMap(Vec<(String, Option<String>)>);
+
+impl EnvironmentMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `Some(None)` for a variable inherited from the host
+    pub fn get(&self, key: &str) -> Option<Option<&str>> {
+        self.0
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_deref())
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.0.iter().any(|(k, _)| k == key)
+    }
+
+    /// Sets `key`, keeping its position when it is already defined. A `None`
+    /// value inherits the variable from the host.
+    pub fn insert(&mut self, key: impl Into<String>, value: Option<String>) {
+        let key = key.into();
+        match self.0.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, existing)) => *existing = value,
+            None => self.0.push((key, value)),
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Option<String>> {
+        let index = self.0.iter().position(|(k, _)| k == key)?;
+        Some(self.0.remove(index).1)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.0.iter().map(|(k, v)| (k.as_str(), v.as_deref()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Whether `key` is declared without a value
+    pub fn is_inherited(&self, key: &str) -> bool {
+        self.get(key) == Some(None)
+    }
+
+    /// Fills in inherited variables from `variables`. Inherited variables
+    /// `variables` doesn't define are left out, as they are for containers.
+    pub fn resolve<V>(&self, variables: &V) -> EnvironmentMap
+    where
+        V: VariableSource + ?Sized,
+    {
+        self.0
+            .iter()
+            .filter_map(|(key, value)| {
+                let value = value.clone().or_else(|| variables.var(key))?;
+                Some((key.clone(), Some(value)))
+            })
+            .collect()
+    }
+
+    /// Converts to the `KEY=VALUE` list syntax
+    pub fn to_list(&self) -> Environment {
+        Environment::List(
+            self.0
+                .iter()
+                .map(|(key, value)| match value {
+                    Some(value) => format!("{key}={value}"),
+                    None => key.clone(),
+                })
+                .collect(),
+        )
+    }
+
+    /// Converts to the mapping syntax
+    pub fn to_kv_pair(&self) -> Environment {
+        let map: Map<String, Option<SingleValue>> = self
+            .0
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone().map(SingleValue::String)))
+            .collect();
+        Environment::KvPair(map)
+    }
+}
+
+impl FromIterator<(String, Option<String>)> for EnvironmentMap {
+    fn from_iter<I: IntoIterator<Item = (String, Option<String>)>>(iter: I) -> Self {
+        let mut map = EnvironmentMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl Extend<(String, Option<String>)> for EnvironmentMap {
+    fn extend<I: IntoIterator<Item = (String, Option<String>)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl IntoIterator for EnvironmentMap {
+    type Item = (String, Option<String>);
+    type IntoIter = std::vec::IntoIter<(String, Option<String>)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl From<&Environment> for EnvironmentMap {
+    fn from(environment: &Environment) -> Self {
+        environment.normalize()
+    }
+}
+
+impl Environment {
+    /// Returns the variables in declaration order. In the list syntax a
+    /// variable declared twice keeps its first position and its last value.
+    pub fn normalize(&self) -> EnvironmentMap {
+        match self {
+            Environment::List(entries) => entries
+                .iter()
+                .map(|entry| match entry.split_once('=') {
+                    Some((key, value)) => (key.to_string(), Some(value.to_string())),
+                    None => (entry.clone(), None),
+                })
+                .collect(),
+            Environment::KvPair(map) => map
+                .iter()
+                .map(|(key, value)| (key.clone(), value.as_ref().map(ToString::to_string)))
+                .collect(),
+        }
+    }
+}
+
+/// Where a variable of the effective container environment is declared
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EnvSource {
+    /// An `env_file` of the service
+    EnvFile(PathBuf),
+    /// The service's `environment`
+    Environment,
+}
+
+/// Where the value of a variable declared without one was taken from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InheritedFrom {
+    /// The environment `docker compose` runs in
+    Host,
+    /// The project's `.env` file or the env files given to the loader
+    Dotenv,
+}
+
+/// A variable of the environment a service's container is started with
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EnvVariable {
+    pub name: String,
+    pub value: String,
+    /// The declaration that takes effect
+    pub source: EnvSource,
+    /// Set when the declaration has no value and the variable is passed
+    /// through
+    pub inherited_from: Option<InheritedFrom>,
+    /// Earlier declarations of the variable, in the order they were overridden
+    pub overrides: Vec<EnvSource>,
+}
+
+impl Project {
+    /// Computes the environment of `service`'s container.
+    ///
+    /// The service's `env_file`s are read in order, later files overriding
+    /// earlier ones, and `environment` overrides them all. Variables declared
+    /// without a value are taken from `host`, then from the project's
+    /// [`dotenv`](Project::dotenv), and left out when neither defines them.
+    /// Env files are interpolated with the same variables.
+    pub fn service_environment<V>(
+        &self,
+        service: &Service,
+        host: &V,
+    ) -> Result<Vec<EnvVariable>, LoadError>
+    where
+        V: VariableSource + ?Sized,
+    {
+        let inherit = |name: &str| match host.var(name) {
+            Some(value) => Some((value, InheritedFrom::Host)),
+            None => self
+                .dotenv
+                .get(name)
+                .map(|value| (value.to_string(), InheritedFrom::Dotenv)),
+        };
+        let mut variables: Vec<EnvVariable> = Vec::new();
+        let mut set = |name: String, value: String, source: EnvSource, inherited_from| {
+            let variable = EnvVariable {
+                name,
+                value,
+                source,
+                inherited_from,
+                overrides: Vec::new(),
+            };
+            match variables.iter_mut().find(|v| v.name == variable.name) {
+                Some(existing) => {
+                    let previous = std::mem::replace(existing, variable);
+                    existing.overrides = previous.overrides;
+                    existing.overrides.push(previous.source);
+                }
+                None => variables.push(variable),
+            }
+        };
+
+        let env_files = match &service.env_file {
+            Some(StringOrList::Simple(path)) => vec![path.clone()],
+            Some(StringOrList::List(paths)) => paths.clone(),
+            None => Vec::new(),
+        };
+        for path in env_files {
+            let path = self.resolve_path(path);
+            let content = std::fs::read_to_string(&path).map_err(|source| LoadError::Io {
+                path: path.clone(),
+                source,
+            })?;
+            let parsed = Dotenv::parse_with(&content, &(host, &self.dotenv));
+            if !parsed.errors.is_empty() {
+                return Err(LoadError::EnvFile {
+                    path,
+                    errors: parsed.errors,
+                });
+            }
+            for (name, value) in parsed.vars {
+                let source = EnvSource::EnvFile(path.clone());
+                match value {
+                    Some(value) => set(name, value, source, None),
+                    None => {
+                        if let Some((value, from)) = inherit(&name) {
+                            set(name, value, source, Some(from));
+                        }
+                    }
+                }
+            }
+        }
+
+        for (name, value) in service.environment.normalize() {
+            match value {
+                Some(value) => set(name, value, EnvSource::Environment, None),
+                None => {
+                    if let Some((value, from)) = inherit(&name) {
+                        set(name, value, EnvSource::Environment, Some(from));
+                    }
+                }
+            }
+        }
+        Ok(variables)
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::include::rebase_service_paths;
+use crate::interpolation::VariableSource;
+use crate::loader::{clean_path, read_compose_file, LoadError};
+use crate::{Compose, Service};
+
+impl Compose {
+    /// Replaces every service using `extends` with the fully expanded service.
+    ///
+    /// `path` is the file this model was read from, `extends.file` entries are
+    /// resolved relative to its directory. Extended services coming from other
+    /// files have their relative paths rewritten to stay valid from `path`.
+    pub fn resolve_extends(&mut self, path: impl AsRef<Path>) -> Result<(), LoadError> {
+        self.resolve_extends_with(path.as_ref(), None)
+    }
+
+    /// Like [`resolve_extends`](Self::resolve_extends), interpolating other
+    /// files read along the way with `variables`
+    pub(crate) fn resolve_extends_with(
+        &mut self,
+        path: &Path,
+        variables: Option<&dyn VariableSource>,
+    ) -> Result<(), LoadError> {
+        let path = clean_path(path);
+        let mut resolver = ExtendsResolver {
+            files: HashMap::new(),
+            variables,
+        };
+        resolver.files.insert(path.clone(), self.clone());
+
+        let names: Vec<String> = self.services.0.keys().cloned().collect();
+        for name in names {
+            let extends = match self.services.0.get(&name) {
+                Some(Some(service)) => !service.extends.is_empty(),
+                _ => false,
+            };
+            if extends {
+                let service = resolver.resolve(&path, &name, &mut Vec::new())?;
+                self.services.0.insert(name, Some(service));
+            }
+        }
+        Ok(())
+    }
+}
+
+struct ExtendsResolver<'a> {
+    /// Models read so far, keyed by the file they were read from
+    files: HashMap<PathBuf, Compose>,
+    variables: Option<&'a dyn VariableSource>,
+}
+
+impl ExtendsResolver<'_> {
+    /// Returns `name` from `path` with its `extends` chain applied. `stack`
+    /// holds the services being resolved and is used to detect cycles.
+    fn resolve(
+        &mut self,
+        path: &Path,
+        name: &str,
+        stack: &mut Vec<(PathBuf, String)>,
+    ) -> Result<Service, LoadError> {
+        let key = (path.to_path_buf(), name.to_string());
+        if stack.contains(&key) {
+            let mut cycle = stack.clone();
+            cycle.push(key);
+            return Err(LoadError::ExtendsCycle(cycle));
+        }
+
+        let mut service = self.service(path, name)?;
+        if service.extends.is_empty() {
+            return Ok(service);
+        }
+
+        let base_name =
+            service
+                .extends
+                .get("service")
+                .cloned()
+                .ok_or_else(|| LoadError::InvalidExtends {
+                    path: path.to_path_buf(),
+                    service: name.to_string(),
+                })?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let base_path = match service.extends.get("file") {
+            Some(file) => clean_path(&dir.join(file)),
+            None => path.to_path_buf(),
+        };
+
+        stack.push(key);
+        let mut base = self.resolve(&base_path, &base_name, stack)?;
+        stack.pop();
+
+        if base_path != path {
+            let base_dir = base_path.parent().unwrap_or(Path::new(""));
+            let relative_dir = base_dir.strip_prefix(dir).unwrap_or(base_dir);
+            rebase_service_paths(&mut base, relative_dir);
+        }
+
+        service.extends = Default::default();
+        Ok(base.merge(service))
+    }
+
+    fn service(&mut self, path: &Path, name: &str) -> Result<Service, LoadError> {
+        if !self.files.contains_key(path) {
+            let compose = read_compose_file(path, self.variables)?;
+            self.files.insert(path.to_path_buf(), compose);
+        }
+        self.files[path]
+            .services
+            .0
+            .get(name)
+            .map(|service| service.clone().unwrap_or_default())
+            .ok_or_else(|| LoadError::UnknownService {
+                path: path.to_path_buf(),
+                service: name.to_string(),
+            })
+    }
+}
@@ -0,0 +1,237 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::{DependsCondition, DependsOnOptions, Map, Service, Services};
+
+/// How a service came to depend on another one
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DependencyKind {
+    DependsOn,
+    /// `links: [service]` or `links: [service:alias]`
+    Link,
+    /// `volumes_from: [service]` or `volumes_from: [service:name]`
+    VolumesFrom,
+    /// `network_mode: service:name`
+    NetworkMode,
+    /// `ipc: service:name`
+    Ipc,
+    /// `pid: service:name`
+    Pid,
+}
+
+/// An edge of the dependency graph
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dependency {
+    pub service: String,
+    pub kind: DependencyKind,
+    /// The condition declared in `depends_on`. Implicit dependencies wait for
+    /// `service_started` and are required.
+    pub condition: DependsCondition,
+}
+
+impl Dependency {
+    fn implicit(service: &str, kind: DependencyKind) -> Self {
+        Dependency {
+            service: service.to_string(),
+            kind,
+            condition: DependsCondition {
+                condition: "service_started".to_string(),
+                restart: None,
+                required: Some(true),
+            },
+        }
+    }
+
+    /// Whether the dependent service can't start without this dependency
+    pub fn is_required(&self) -> bool {
+        self.condition.required != Some(false)
+    }
+}
+
+impl Service {
+    /// The services this one depends on, from `depends_on` followed by the
+    /// implicit dependencies of `links`, `volumes_from`, `network_mode`, `ipc`
+    /// and `pid`. A service is only listed once, with its first edge.
+    pub fn dependencies(&self) -> Vec<Dependency> {
+        let mut dependencies: Vec<Dependency> = match &self.depends_on {
+            DependsOnOptions::Simple(services) => services
+                .iter()
+                .map(|s| Dependency::implicit(s, DependencyKind::DependsOn))
+                .collect(),
+            DependsOnOptions::Conditional(services) => services
+                .iter()
+                .map(|(s, condition)| Dependency {
+                    service: s.clone(),
+                    kind: DependencyKind::DependsOn,
+                    condition: condition.clone(),
+                })
+                .collect(),
+        };
+
+        let links = self
+            .links
+            .iter()
+            .map(|link| (link_service(link), DependencyKind::Link));
+        let volumes_from = self.volumes_from.iter().filter_map(|source| {
+            Some((volumes_from_service(source)?, DependencyKind::VolumesFrom))
+        });
+        let namespaces = [
+            (&self.network_mode, DependencyKind::NetworkMode),
+            (&self.ipc, DependencyKind::Ipc),
+            (&self.pid, DependencyKind::Pid),
+        ]
+        .into_iter()
+        .filter_map(|(mode, kind)| Some((mode_service(mode.as_deref()?)?, kind)));
+
+        for (name, kind) in links.chain(volumes_from).chain(namespaces) {
+            if !name.is_empty() && !dependencies.iter().any(|d| d.service == name) {
+                dependencies.push(Dependency::implicit(name, kind));
+            }
+        }
+        dependencies
+    }
+}
+
+/// The service of a `links` entry, `service` or `service:alias`
+pub(crate) fn link_service(link: &str) -> &str {
+    link.split(':').next().unwrap_or(link)
+}
+
+/// The service of a `volumes_from` entry, `None` for `container:name` entries
+pub(crate) fn volumes_from_service(source: &str) -> Option<&str> {
+    let mut parts = source.split(':');
+    match parts.next() {
+        Some("container") => None,
+        Some("service") => parts.next(),
+        name => name,
+    }
+}
+
+/// The service of a `network_mode`, `ipc` or `pid` value such as `service:db`
+pub(crate) fn mode_service(mode: &str) -> Option<&str> {
+    mode.strip_prefix("service:")
+}
+
+/// The dependencies between the services of a project
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DependencyGraph {
+    edges: Map<String, Vec<Dependency>>,
+}
+
+/// A dependency cycle, listing the services along it with the first service
+/// repeated at the end
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DependencyCycle(pub Vec<String>);
+
+impl fmt::Display for DependencyCycle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "dependency cycle detected: {}", self.0.join(" -> "))
+    }
+}
+
+impl std::error::Error for DependencyCycle {}
+
+impl Services {
+    pub fn dependency_graph(&self) -> DependencyGraph {
+        DependencyGraph {
+            edges: self
+                .0
+                .iter()
+                .map(|(name, service)| {
+                    let dependencies = service
+                        .as_ref()
+                        .map(Service::dependencies)
+                        .unwrap_or_default();
+                    (name.clone(), dependencies)
+                })
+                .collect(),
+        }
+    }
+}
+
+impl DependencyGraph {
+    /// The services in the graph
+    pub fn services(&self) -> impl Iterator<Item = &str> {
+        self.edges.keys().map(String::as_str)
+    }
+
+    /// The edges from `service` to the services it depends on
+    pub fn dependencies(&self, service: &str) -> &[Dependency] {
+        self.edges.get(service).map_or(&[], Vec::as_slice)
+    }
+
+    /// The services depending on `service`
+    pub fn dependents(&self, service: &str) -> Vec<&str> {
+        self.edges
+            .iter()
+            .filter(|(_, dependencies)| dependencies.iter().any(|d| d.service == service))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// Groups the services into waves that can be started in parallel, each
+    /// wave only depending on the ones before it. Dependencies on services
+    /// that are not part of the graph are ignored.
+    pub fn start_waves(&self) -> Result<Vec<Vec<String>>, DependencyCycle> {
+        let mut started: HashSet<String> = HashSet::new();
+        let mut waves = Vec::new();
+        while started.len() < self.edges.len() {
+            let wave: Vec<String> = self
+                .edges
+                .iter()
+                .filter(|(name, dependencies)| {
+                    !started.contains(*name)
+                        && dependencies.iter().all(|d| {
+                            started.contains(&d.service) || !self.edges.contains_key(&d.service)
+                        })
+                })
+                .map(|(name, _)| name.clone())
+                .collect();
+            if wave.is_empty() {
+                return Err(self.find_cycle(&started));
+            }
+            started.extend(wave.iter().cloned());
+            waves.push(wave);
+        }
+        Ok(waves)
+    }
+
+    /// The order services are started in, dependencies first
+    pub fn start_order(&self) -> Result<Vec<String>, DependencyCycle> {
+        Ok(self.start_waves()?.into_iter().flatten().collect())
+    }
+
+    /// The order services are stopped in, dependents first
+    pub fn stop_order(&self) -> Result<Vec<String>, DependencyCycle> {
+        let mut order = self.start_order()?;
+        order.reverse();
+        Ok(order)
+    }
+
+    /// Finds a cycle among the services that could not be started
+    fn find_cycle(&self, started: &HashSet<String>) -> DependencyCycle {
+        let mut path: Vec<&str> = Vec::new();
+        let mut current = self
+            .services()
+            .find(|name| !started.contains(*name))
+            .unwrap_or_default();
+        // Every blocked service has a blocked dependency, so following them
+        // eventually revisits a service
+        loop {
+            if let Some(start) = path.iter().position(|name| *name == current) {
+                let mut cycle: Vec<String> = path[start..].iter().map(|s| s.to_string()).collect();
+                cycle.push(current.to_string());
+                return DependencyCycle(cycle);
+            }
+            path.push(current);
+            match self
+                .dependencies(current)
+                .iter()
+                .find(|d| self.edges.contains_key(&d.service) && !started.contains(&d.service))
+            {
+                Some(dependency) => current = &dependency.service,
+                None => return DependencyCycle(path.iter().map(|s| s.to_string()).collect()),
+            }
+        }
+    }
+}
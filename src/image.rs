@@ -0,0 +1,277 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::Service;
+
+const DEFAULT_REGISTRY: &str = "docker.io";
+const LEGACY_DEFAULT_REGISTRY: &str = "index.docker.io";
+const OFFICIAL_NAMESPACE: &str = "library";
+const DEFAULT_TAG: &str = "latest";
+
+/// A parsed image reference, `[registry[:port]/][namespace/]repository[:tag][@digest]`.
+///
+/// Fields hold the reference as written, [`normalize`](Self::normalize)
+/// applies Docker's defaults so that `nginx` becomes
+/// `docker.io/library/nginx:latest`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ImageRef {
+    /// Registry host with an optional port, `None` for the default registry
+    pub registry: Option<String>,
+    /// Repository path below the registry, e.g. `library/nginx` or `nginx`
+    pub path: String,
+    pub tag: Option<String>,
+    /// Content digest such as `sha256:...`
+    pub digest: Option<String>,
+}
+
+impl ImageRef {
+    pub fn parse(reference: &str) -> Result<ImageRef, ImageRefParseError> {
+        let error = |message: &str| ImageRefParseError {
+            reference: reference.to_string(),
+            message: message.to_string(),
+        };
+
+        let (name, digest) = match reference.split_once('@') {
+            Some((name, digest)) => {
+                if !is_valid_digest(digest) {
+                    return Err(error("invalid digest"));
+                }
+                (name, Some(digest.to_string()))
+            }
+            None => (reference, None),
+        };
+        let last_slash = name.rfind('/').map_or(0, |i| i + 1);
+        let (name, tag) = match name[last_slash..].rfind(':') {
+            Some(colon) => {
+                let (name, tag) = name.split_at(last_slash + colon);
+                let tag = &tag[1..];
+                if !is_valid_tag(tag) {
+                    return Err(error("invalid tag"));
+                }
+                (name, Some(tag.to_string()))
+            }
+            None => (name, None),
+        };
+
+        let (registry, path) = match name.split_once('/') {
+            Some((first, rest))
+                if first.contains(['.', ':'])
+                    || first == "localhost"
+                    || first.chars().any(|c| c.is_ascii_uppercase()) =>
+            {
+                (Some(first), rest)
+            }
+            _ => (None, name),
+        };
+        if let Some(registry) = registry {
+            if !is_valid_registry(registry) {
+                return Err(error("invalid registry"));
+            }
+        }
+        if path.is_empty() || !path.split('/').all(is_valid_path_component) {
+            return Err(error(
+                "invalid repository name, it must be lowercase and may contain '.', '_' and '-'",
+            ));
+        }
+
+        Ok(ImageRef {
+            registry: registry.map(str::to_string),
+            path: path.to_string(),
+            tag,
+            digest,
+        })
+    }
+
+    /// Applies Docker's defaults: the `docker.io` registry, the `library`
+    /// namespace for official images and the `latest` tag when neither a tag
+    /// nor a digest is given
+    pub fn normalize(&self) -> ImageRef {
+        let registry = self.registry();
+        let path = if registry == DEFAULT_REGISTRY && !self.path.contains('/') {
+            format!("{OFFICIAL_NAMESPACE}/{}", self.path)
+        } else {
+            self.path.clone()
+        };
+        let tag = match (&self.tag, &self.digest) {
+            (None, None) => Some(DEFAULT_TAG.to_string()),
+            (tag, _) => tag.clone(),
+        };
+        ImageRef {
+            registry: Some(registry.to_string()),
+            path,
+            tag,
+            digest: self.digest.clone(),
+        }
+    }
+
+    /// The registry, including its port, `docker.io` when none is given
+    pub fn registry(&self) -> &str {
+        match self.registry.as_deref() {
+            None | Some(LEGACY_DEFAULT_REGISTRY) => DEFAULT_REGISTRY,
+            Some(registry) => registry,
+        }
+    }
+
+    pub fn registry_host(&self) -> &str {
+        let registry = self.registry();
+        // Keep bracketed IPv6 addresses together
+        match registry.rfind(':') {
+            Some(colon) if !registry[colon..].contains(']') => &registry[..colon],
+            _ => registry,
+        }
+    }
+
+    pub fn registry_port(&self) -> Option<u16> {
+        let registry = self.registry();
+        let port = &registry[self.registry_host().len()..];
+        port.strip_prefix(':')?.parse().ok()
+    }
+
+    /// The path below the registry without the last component, `library` for
+    /// official images on the default registry
+    pub fn namespace(&self) -> Option<&str> {
+        match self.path.rsplit_once('/') {
+            Some((namespace, _)) => Some(namespace),
+            None if self.registry() == DEFAULT_REGISTRY => Some(OFFICIAL_NAMESPACE),
+            None => None,
+        }
+    }
+
+    /// The last component of the path, e.g. `nginx`
+    pub fn repository(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+
+    /// Whether the image is identified by its content digest rather than a
+    /// mutable tag
+    pub fn is_pinned_by_digest(&self) -> bool {
+        self.digest.is_some()
+    }
+
+    /// Returns this reference with `tag`. The digest is dropped as it would
+    /// take precedence over the new tag.
+    pub fn with_tag(&self, tag: impl Into<String>) -> ImageRef {
+        ImageRef {
+            tag: Some(tag.into()),
+            digest: None,
+            ..self.clone()
+        }
+    }
+}
+
+impl FromStr for ImageRef {
+    type Err = ImageRefParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for ImageRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(registry) = &self.registry {
+            write!(f, "{registry}/")?;
+        }
+        write!(f, "{}", self.path)?;
+        if let Some(tag) = &self.tag {
+            write!(f, ":{tag}")?;
+        }
+        if let Some(digest) = &self.digest {
+            write!(f, "@{digest}")?;
+        }
+        Ok(())
+    }
+}
+
+/// An image reference that could not be parsed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImageRefParseError {
+    pub reference: String,
+    pub message: String,
+}
+
+impl fmt::Display for ImageRefParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid image reference {:?}: {}",
+            self.reference, self.message
+        )
+    }
+}
+
+impl std::error::Error for ImageRefParseError {}
+
+impl Service {
+    /// Parses [`image`](Self::image), `None` when the service has no image
+    pub fn image_ref(&self) -> Option<Result<ImageRef, ImageRefParseError>> {
+        self.image.as_deref().map(ImageRef::parse)
+    }
+}
+
+/// Lowercase alphanumerics separated by `.`, `_`, `__` or runs of `-`
+fn is_valid_path_component(component: &str) -> bool {
+    let bytes = component.as_bytes();
+    let is_alnum = |b: &u8| b.is_ascii_lowercase() || b.is_ascii_digit();
+    if !bytes.first().is_some_and(is_alnum) || !bytes.last().is_some_and(is_alnum) {
+        return false;
+    }
+    let mut separator = String::new();
+    for &b in bytes {
+        if is_alnum(&b) {
+            if !matches!(separator.as_str(), "" | "." | "_" | "__")
+                && !separator.bytes().all(|b| b == b'-')
+            {
+                return false;
+            }
+            separator.clear();
+        } else if matches!(b, b'.' | b'_' | b'-') {
+            separator.push(b as char);
+        } else {
+            return false;
+        }
+    }
+    true
+}
+
+fn is_valid_tag(tag: &str) -> bool {
+    let mut chars = tag.chars();
+    tag.len() <= 128
+        && chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+fn is_valid_digest(digest: &str) -> bool {
+    let Some((algorithm, hex)) = digest.split_once(':') else {
+        return false;
+    };
+    !algorithm.is_empty()
+        && algorithm.starts_with(|c: char| c.is_ascii_alphabetic())
+        && algorithm
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '+' | '.'))
+        && hex.len() >= 32
+        && hex.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn is_valid_registry(registry: &str) -> bool {
+    let (host, port) = match registry.rsplit_once(':') {
+        Some((host, port)) if !port.contains(']') => (host, Some(port)),
+        _ => (registry, None),
+    };
+    let valid_host = if let Some(ip) = host.strip_prefix('[') {
+        ip.strip_suffix(']')
+            .is_some_and(|ip| ip.chars().all(|c| c.is_ascii_hexdigit() || c == ':'))
+    } else {
+        !host.is_empty()
+            && host.split('.').all(|label| {
+                !label.is_empty()
+                    && !label.starts_with('-')
+                    && !label.ends_with('-')
+                    && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            })
+    };
+    valid_host && port.is_none_or(|port| port.parse::<u16>().is_ok())
+}
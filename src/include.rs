@@ -0,0 +1,218 @@
+use std::path::{Path, PathBuf};
+
+use crate::interpolation::VariableSource;
+use crate::loader::{clean_path, load_files, read_env_files, LoadError};
+use crate::volumes::is_windows_path;
+use crate::{AdvancedVolumes, BuildStep, Compose, Map, Service, StringOrList, Volumes};
+
+/// Loads every `include` entry of `compose` and imports the resources they
+/// define, leaving `compose.includes` empty.
+///
+/// Include paths are resolved against `working_dir`, the project directory of
+/// the including file. `stack` holds the files currently being loaded and is
+/// used to detect include cycles.
+pub(crate) fn resolve_includes(
+    compose: &mut Compose,
+    working_dir: &Path,
+    variables: Option<&dyn VariableSource>,
+    stack: &mut Vec<PathBuf>,
+) -> Result<(), LoadError> {
+    let Some(includes) = compose.includes.take() else {
+        return Ok(());
+    };
+
+    for include in includes.into_long() {
+        let paths: Vec<PathBuf> = match include.path {
+            Some(StringOrList::Simple(path)) => vec![path],
+            Some(StringOrList::List(paths)) => paths,
+            None => Vec::new(),
+        }
+        .into_iter()
+        .map(|path| clean_path(&working_dir.join(path)))
+        .collect();
+        let Some(main_file) = paths.first() else {
+            continue;
+        };
+
+        let project_directory = match &include.project_directory {
+            Some(dir) => clean_path(&working_dir.join(dir)),
+            None => main_file
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default(),
+        };
+
+        // The included files are interpolated with the variables of their own
+        // env files, the including project's variables take precedence
+        let env_files = match include.env_file {
+            Some(StringOrList::Simple(path)) => vec![path],
+            Some(StringOrList::List(paths)) => paths,
+            None => Vec::new(),
+        };
+        let env_files: Vec<PathBuf> = if env_files.is_empty() {
+            let default = project_directory.join(".env");
+            default.is_file().then_some(default).into_iter().collect()
+        } else {
+            env_files
+                .into_iter()
+                .map(|path| clean_path(&working_dir.join(path)))
+                .collect()
+        };
+        // Without interpolation the env files aren't needed and aren't read
+        let mut included = match variables {
+            Some(variables) => {
+                let dotenv = read_env_files(&env_files, variables)?;
+                let scoped = (variables, &dotenv);
+                load_files(&paths, &project_directory, Some(&scoped), stack)?
+            }
+            None => load_files(&paths, &project_directory, None, stack)?,
+        };
+
+        let relative_dir = project_directory
+            .strip_prefix(working_dir)
+            .unwrap_or(&project_directory);
+        rebase_paths(&mut included, relative_dir);
+        import_resources(compose, included, main_file)?;
+    }
+    Ok(())
+}
+
+fn import_resources(target: &mut Compose, source: Compose, path: &Path) -> Result<(), LoadError> {
+    import("services", &mut target.services.0, source.services.0, path)?;
+    import("networks", &mut target.networks.0, source.networks.0, path)?;
+    import("volumes", &mut target.volumes.0, source.volumes.0, path)?;
+    if let Some(secrets) = source.secrets {
+        let target = target.secrets.get_or_insert_with(Default::default);
+        import("secrets", &mut target.0, secrets.0, path)?;
+    }
+    if let Some(configs) = source.configs {
+        let target = target.configs.get_or_insert_with(Default::default);
+        import("configs", &mut target.0, configs.0, path)?;
+    }
+    Ok(())
+}
+
+/// Moves the entries of `source` into `target`. A name defined in both is only
+/// accepted when both definitions are identical.
+fn import<V: PartialEq>(
+    kind: &'static str,
+    target: &mut Map<String, V>,
+    source: Map<String, V>,
+    path: &Path,
+) -> Result<(), LoadError> {
+    for (name, value) in source {
+        match target.get(&name) {
+            Some(existing) if *existing != value => {
+                return Err(LoadError::IncludeConflict {
+                    path: path.to_path_buf(),
+                    kind,
+                    name,
+                });
+            }
+            Some(_) => {}
+            None => {
+                target.insert(name, value);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Rewrites the relative paths of an included model so they stay valid from
+/// the including file's project directory. `dir` is the included project
+/// directory, relative to the including one when possible.
+fn rebase_paths(compose: &mut Compose, dir: &Path) {
+    if dir.as_os_str().is_empty() {
+        return;
+    }
+
+    for service in compose.services.0.values_mut().flatten() {
+        rebase_service_paths(service, dir);
+    }
+
+    for secret in compose.secrets.iter_mut().flat_map(|s| s.0.values_mut()) {
+        if let Some(file) = secret.as_mut().and_then(|s| s.file.as_mut()) {
+            *file = rebase(file, dir);
+        }
+    }
+
+    for config in compose.configs.iter_mut().flat_map(|c| c.0.values_mut()) {
+        if let Some(file) = config.as_mut().and_then(|c| c.file.as_mut()) {
+            *file = rebase(file, dir);
+        }
+    }
+}
+
+/// Rewrites the relative paths used by `service` so they are relative to the
+/// directory `dir` is relative to.
+pub(crate) fn rebase_service_paths(service: &mut Service, dir: &Path) {
+    if dir.as_os_str().is_empty() {
+        return;
+    }
+    match &mut service.build_ {
+        Some(BuildStep::Simple(context)) => rebase_build_context(context, dir),
+        Some(BuildStep::Advanced(build)) => rebase_build_context(&mut build.context, dir),
+        None => {}
+    }
+    if let Some(env_file) = &mut service.env_file {
+        rebase_string_or_list(env_file, dir);
+    }
+    for volume in &mut service.volumes {
+        match volume {
+            Volumes::Simple(spec) => {
+                let Ok(mut volume) = AdvancedVolumes::parse_short(spec) else {
+                    continue;
+                };
+                match &mut volume.source {
+                    Some(source) if volume._type == "bind" && source.starts_with('.') => {
+                        *source = rebase(source, dir);
+                    }
+                    _ => continue,
+                }
+                if let Some(rebased) = volume.to_short() {
+                    *spec = rebased;
+                }
+            }
+            Volumes::Advanced(advanced) if advanced._type == "bind" => {
+                if let Some(source) = &mut advanced.source {
+                    *source = rebase(source, dir);
+                }
+            }
+            Volumes::Advanced(_) => {}
+        }
+    }
+    for rule in service.develop.iter_mut().flat_map(|d| &mut d.watch) {
+        rule.path = rebase(&rule.path, dir);
+    }
+}
+
+fn rebase_build_context(context: &mut String, dir: &Path) {
+    // Remote contexts such as git repositories or tarball URLs are left alone
+    if context.contains("://") || context.starts_with("git@") {
+        return;
+    }
+    *context = rebase(context, dir);
+}
+
+fn rebase_string_or_list(value: &mut StringOrList, dir: &Path) {
+    match value {
+        StringOrList::Simple(path) => *path = rebase(path, dir),
+        StringOrList::List(paths) => {
+            for path in paths {
+                *path = rebase(path, dir);
+            }
+        }
+    }
+}
+
+fn rebase(path: &str, dir: &Path) -> String {
+    if path.starts_with('~') || Path::new(path).is_absolute() || is_windows_path(path) {
+        return path.to_string();
+    }
+    let rebased = clean_path(&dir.join(path)).display().to_string();
+    if dir.is_absolute() || rebased.starts_with('.') {
+        rebased
+    } else {
+        format!("./{rebased}")
+    }
+}
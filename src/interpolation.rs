@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+use std::fmt;
+
+#[cfg(feature = "indexmap")]
+use indexmap::IndexMap;
+#[cfg(feature = "norway")]
+use serde_norway as serde_yaml;
+#[cfg(feature = "yml")]
+use serde_yml as serde_yaml;
+
+use serde_yaml::Value;
+
+/// Provides the values `${VAR}` references are replaced with
+pub trait VariableSource {
+    /// Returns the value of `name`, or `None` when it is unset
+    fn var(&self, name: &str) -> Option<String>;
+}
+
+/// Reads variables from the environment of the current process
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ProcessEnv;
+
+impl VariableSource for ProcessEnv {
+    fn var(&self, name: &str) -> Option<String> {
+        std::env::var(name).ok()
+    }
+}
+
+impl VariableSource for HashMap<String, String> {
+    fn var(&self, name: &str) -> Option<String> {
+        self.get(name).cloned()
+    }
+}
+
+#[cfg(feature = "indexmap")]
+impl VariableSource for IndexMap<String, String> {
+    fn var(&self, name: &str) -> Option<String> {
+        self.get(name).cloned()
+    }
+}
+
+impl<T: VariableSource + ?Sized> VariableSource for &T {
+    fn var(&self, name: &str) -> Option<String> {
+        (**self).var(name)
+    }
+}
+
+/// Looks variables up in the first source, falling back to the second one
+impl<A: VariableSource, B: VariableSource> VariableSource for (A, B) {
+    fn var(&self, name: &str) -> Option<String> {
+        self.0.var(name).or_else(|| self.1.var(name))
+    }
+}
+
+/// Replaces `$VAR` and `${VAR}` references in `template`.
+///
+/// Supports the `${VAR:-default}`, `${VAR-default}`, `${VAR:?error}`,
+/// `${VAR?error}`, `${VAR:+alternative}` and `${VAR+alternative}` forms with
+/// nested references in the default, error and alternative texts. `$$` is an
+/// escaped `$`. Unset variables without a default expand to an empty string.
+pub fn interpolate<V>(template: &str, variables: &V) -> Result<String, InterpolationError>
+where
+    V: VariableSource + ?Sized,
+{
+    substitute(template, variables).map_err(|kind| InterpolationError {
+        path: String::new(),
+        kind,
+    })
+}
+
+/// Interpolates every string scalar in `value`. Mapping keys are left as is.
+///
+/// The returned error carries the YAML path of the failing value, such as
+/// `services.web.ports[0]`.
+pub fn interpolate_value<V>(value: &mut Value, variables: &V) -> Result<(), InterpolationError>
+where
+    V: VariableSource + ?Sized,
+{
+    let mut path = String::new();
+    interpolate_at(value, variables, &mut path)
+}
+
+fn interpolate_at<V>(
+    value: &mut Value,
+    variables: &V,
+    path: &mut String,
+) -> Result<(), InterpolationError>
+where
+    V: VariableSource + ?Sized,
+{
+    match value {
+        Value::String(s) => {
+            *s = substitute(s, variables).map_err(|kind| InterpolationError {
+                path: path.clone(),
+                kind,
+            })?;
+        }
+        Value::Sequence(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                let len = path.len();
+                path.push_str(&format!("[{i}]"));
+                interpolate_at(item, variables, path)?;
+                path.truncate(len);
+            }
+        }
+        Value::Mapping(mapping) => {
+            for (key, item) in mapping.iter_mut() {
+                let len = path.len();
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                match key {
+                    Value::String(key) => path.push_str(key),
+                    other => {
+                        path.push_str(serde_yaml::to_string(other).unwrap_or_default().trim_end())
+                    }
+                }
+                interpolate_at(item, variables, path)?;
+                path.truncate(len);
+            }
+        }
+        Value::Tagged(tagged) => interpolate_at(&mut tagged.value, variables, path)?,
+        Value::Null | Value::Bool(_) | Value::Number(_) => {}
+    }
+    Ok(())
+}
+
+fn substitute<V>(template: &str, variables: &V) -> Result<String, InterpolationErrorKind>
+where
+    V: VariableSource + ?Sized,
+{
+    let invalid = || InterpolationErrorKind::InvalidSyntax(template.to_string());
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('$') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        if let Some(after) = after.strip_prefix('$') {
+            result.push('$');
+            rest = after;
+        } else if let Some(braced) = after.strip_prefix('{') {
+            let end = closing_brace(braced).ok_or_else(invalid)?;
+            result.push_str(&expand(&braced[..end], variables).ok_or_else(invalid)??);
+            rest = &braced[end + 1..];
+        } else {
+            let len = name_len(after);
+            if len == 0 {
+                return Err(invalid());
+            }
+            result.push_str(&variables.var(&after[..len]).unwrap_or_default());
+            rest = &after[len..];
+        }
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Expands the contents of a `${...}` expression, returns `None` when the
+/// expression is malformed
+fn expand<V>(expression: &str, variables: &V) -> Option<Result<String, InterpolationErrorKind>>
+where
+    V: VariableSource + ?Sized,
+{
+    let len = name_len(expression);
+    if len == 0 {
+        return None;
+    }
+    let (name, operation) = expression.split_at(len);
+    let value = variables.var(name);
+
+    let result = if operation.is_empty() {
+        Ok(value.unwrap_or_default())
+    } else if let Some(default) = operation.strip_prefix(":-") {
+        fallback(value.filter(|v| !v.is_empty()), default, variables)
+    } else if let Some(default) = operation.strip_prefix('-') {
+        fallback(value, default, variables)
+    } else if let Some(alternative) = operation.strip_prefix(":+") {
+        match value.filter(|v| !v.is_empty()) {
+            Some(_) => substitute(alternative, variables),
+            None => Ok(String::new()),
+        }
+    } else if let Some(alternative) = operation.strip_prefix('+') {
+        match value {
+            Some(_) => substitute(alternative, variables),
+            None => Ok(String::new()),
+        }
+    } else if let Some(message) = operation.strip_prefix(":?") {
+        required(name, value.filter(|v| !v.is_empty()), message, variables)
+    } else if let Some(message) = operation.strip_prefix('?') {
+        required(name, value, message, variables)
+    } else {
+        return None;
+    };
+    Some(result)
+}
+
+fn fallback<V>(
+    value: Option<String>,
+    default: &str,
+    variables: &V,
+) -> Result<String, InterpolationErrorKind>
+where
+    V: VariableSource + ?Sized,
+{
+    match value {
+        Some(value) => Ok(value),
+        None => substitute(default, variables),
+    }
+}
+
+fn required<V>(
+    name: &str,
+    value: Option<String>,
+    message: &str,
+    variables: &V,
+) -> Result<String, InterpolationErrorKind>
+where
+    V: VariableSource + ?Sized,
+{
+    match value {
+        Some(value) => Ok(value),
+        None => Err(InterpolationErrorKind::MissingVariable {
+            name: name.to_string(),
+            message: substitute(message, variables)?,
+        }),
+    }
+}
+
+/// Whether `s` references variables, so it can only be parsed once it has been
+/// interpolated
+pub(crate) fn has_variables(s: &str) -> bool {
+    s.contains('$')
+}
+
+/// Length of the variable name at the start of `s`
+fn name_len(s: &str) -> usize {
+    let mut chars = s.char_indices();
+    match chars.next() {
+        Some((_, c)) if c == '_' || c.is_ascii_alphabetic() => {}
+        _ => return 0,
+    }
+    chars
+        .find(|(_, c)| !(*c == '_' || c.is_ascii_alphanumeric()))
+        .map_or(s.len(), |(i, _)| i)
+}
+
+/// Byte offset of the `}` closing an expression whose `${` precedes `s`
+fn closing_brace(s: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'$' if bytes.get(i + 1) == Some(&b'$') => i += 1,
+            b'$' if bytes.get(i + 1) == Some(&b'{') => {
+                depth += 1;
+                i += 1;
+            }
+            b'}' if depth == 0 => return Some(i),
+            b'}' => depth -= 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// The result of a failed interpolation
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InterpolationError {
+    /// YAML path of the value that failed, e.g. `services.web.image`. Empty
+    /// when a plain string was interpolated
+    pub path: String,
+    pub kind: InterpolationErrorKind,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum InterpolationErrorKind {
+    /// A variable marked as required with `?` or `:?` has no value
+    MissingVariable { name: String, message: String },
+    /// The template contains a malformed reference
+    InvalidSyntax(String),
+}
+
+impl fmt::Display for InterpolationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.path.is_empty() {
+            write!(f, "{}: ", self.path)?;
+        }
+        match &self.kind {
+            InterpolationErrorKind::MissingVariable { name, message } if message.is_empty() => {
+                write!(f, "required variable {name} is missing a value")
+            }
+            InterpolationErrorKind::MissingVariable { name, message } => {
+                write!(f, "required variable {name} is missing a value: {message}")
+            }
+            InterpolationErrorKind::InvalidSyntax(template) => {
+                write!(f, "invalid interpolation format {template:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for InterpolationError {}
@@ -15,6 +15,43 @@ use std::str::FromStr;
 
 use serde_yaml::Value;
 
+#[cfg(feature = "indexmap")]
+pub(crate) type Map<K, V> = IndexMap<K, V>;
+#[cfg(not(feature = "indexmap"))]
+pub(crate) type Map<K, V> = HashMap<K, V>;
+
+mod byte_size;
+mod dotenv;
+mod duration;
+mod environment;
+mod extends;
+mod graph;
+mod image;
+mod include;
+mod interpolation;
+mod loader;
+mod merge;
+mod ports;
+mod profiles;
+mod validate;
+mod volumes;
+
+pub use byte_size::{ByteSize, ByteSizeParseError, MemswapLimit};
+pub use dotenv::{Dotenv, DotenvError};
+pub use duration::{ComposeDuration, DurationParseError};
+pub use environment::{EnvSource, EnvVariable, EnvironmentMap, InheritedFrom};
+pub use graph::{Dependency, DependencyCycle, DependencyGraph, DependencyKind};
+pub use image::{ImageRef, ImageRefParseError};
+pub use interpolation::{
+    interpolate, interpolate_value, InterpolationError, InterpolationErrorKind, ProcessEnv,
+    VariableSource,
+};
+pub use loader::{ComposeLoader, LoadError, Project};
+pub use ports::PortParseError;
+pub use profiles::{ActiveProfiles, DisabledReference, ProfileSelection};
+pub use validate::{Diagnostic, Severity};
+pub use volumes::VolumeParseError;
+
 #[allow(clippy::large_enum_variant)]
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(untagged)]
@@ -55,6 +92,8 @@ pub struct Compose {
     pub service: Option<Service>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub secrets: Option<ComposeSecrets>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub configs: Option<ComposeConfigs>,
     #[cfg(feature = "indexmap")]
     #[serde(flatten, skip_serializing_if = "IndexMap::is_empty")]
     pub extensions: IndexMap<Extension, Value>,
@@ -117,6 +156,20 @@ impl Includes {
             Includes::Long(xs) => xs.is_empty(),
         }
     }
+
+    /// Converts short entries into the equivalent long syntax
+    pub fn into_long(self) -> Vec<Include> {
+        match self {
+            Includes::Short(xs) => xs
+                .into_iter()
+                .map(|path| Include {
+                    path: Some(StringOrList::Simple(path)),
+                    ..Default::default()
+                })
+                .collect(),
+            Includes::Long(xs) => xs,
+        }
+    }
 }
 
 impl Default for Includes {
@@ -132,6 +185,8 @@ pub struct Service {
     pub hostname: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub domainname: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mac_address: Option<String>,
     #[serde(default, skip_serializing_if = "std::ops::Not::not")]
     pub privileged: bool,
     #[serde(default, skip_serializing_if = "std::ops::Not::not")]
@@ -181,7 +236,7 @@ pub struct Service {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub env_file: Option<EnvFile>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub stop_grace_period: Option<String>,
+    pub stop_grace_period: Option<ComposeDuration>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub profiles: Vec<String>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
@@ -229,7 +284,7 @@ pub struct Service {
     #[serde(default, skip_serializing_if = "std::ops::Not::not")]
     pub stdin_open: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub shm_size: Option<String>,
+    pub shm_size: Option<ByteSize>,
     #[cfg(feature = "indexmap")]
     #[serde(flatten, skip_serializing_if = "IndexMap::is_empty")]
     pub extensions: IndexMap<Extension, Value>,
@@ -248,18 +303,62 @@ pub struct Service {
     pub security_opt: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub secrets: Option<Secrets>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub configs: Option<Configs>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub pull_policy: Option<PullPolicy>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub cgroup_parent: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub mem_limit: Option<String>,
+    pub mem_limit: Option<ByteSize>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub mem_reservation: Option<String>,
+    pub mem_reservation: Option<ByteSize>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub mem_swappiness: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memswap_limit: Option<MemswapLimit>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub oom_kill_disable: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oom_score_adj: Option<i16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pids_limit: Option<i64>,
+    #[serde(
+        default,
+        deserialize_with = "de_cpus",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub cpus: Option<Cpus>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_shares: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_quota: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_period: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpuset: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_count: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_percent: Option<u8>,
+    /// Microseconds as an integer or a duration such as `400ms`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_rt_runtime: Option<StringOrUnsigned>,
+    /// Microseconds as an integer or a duration such as `1400us`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_rt_period: Option<StringOrUnsigned>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blkio_config: Option<BlkioConfig>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub runtime: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub develop: Option<Develop>,
+    /// Commands run in the container after it has started
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_start: Vec<ServiceHook>,
+    /// Commands run in the container before it is stopped
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_stop: Vec<ServiceHook>,
 }
 
 #[cfg(feature = "indexmap")]
@@ -330,6 +429,52 @@ where
     ))
 }
 
+/// The number of CPUs a service may use
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum Cpus {
+    Number(f64),
+    /// A string referencing variables, kept until it is interpolated
+    Unresolved(String),
+}
+
+impl Cpus {
+    /// Returns the number of CPUs, `None` if it references variables that have
+    /// not been interpolated
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Number(cpus) => Some(*cpus),
+            Self::Unresolved(_) => None,
+        }
+    }
+}
+
+/// Accepts `cpus` as a number or a numeric string such as `"0.5"`
+fn de_cpus<'de, D>(deserializer: D) -> Result<Option<Cpus>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Value::deserialize(deserializer)?;
+    if value.is_null() {
+        return Ok(None);
+    }
+    if let Some(cpus) = value.as_f64() {
+        return Ok(Some(Cpus::Number(cpus)));
+    }
+    if let Some(s) = value.as_str() {
+        if interpolation::has_variables(s) {
+            return Ok(Some(Cpus::Unresolved(s.to_string())));
+        }
+        if let Ok(cpus) = s.trim().parse() {
+            return Ok(Some(Cpus::Number(cpus)));
+        }
+    }
+
+    Err(serde::de::Error::custom(
+        "cpus must be a number or a numeric string".to_string(),
+    ))
+}
+
 impl Service {
     pub fn image(&self) -> &str {
         self.image.as_deref().unwrap_or_default()
@@ -615,8 +760,23 @@ impl Networks {
             Self::Advanced(n) => n.0.is_empty(),
         }
     }
+
+    /// Returns the networks in the mapping syntax, names from the list syntax
+    /// map to no settings
+    pub fn normalize(&self) -> AdvancedNetworks {
+        match self {
+            Self::Simple(names) => AdvancedNetworks(
+                names
+                    .iter()
+                    .map(|name| (name.clone(), MapOrEmpty::Empty))
+                    .collect(),
+            ),
+            Self::Advanced(networks) => networks.clone(),
+        }
+    }
 }
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
 #[serde(untagged)]
 pub enum BuildStep {
@@ -628,6 +788,8 @@ pub enum BuildStep {
 #[serde(deny_unknown_fields)]
 #[builder(setter(into), default)]
 pub struct AdvancedBuildStep {
+    /// The build context, empty when omitted, which compose treats as `.`
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub context: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub dockerfile: Option<String>,
@@ -636,15 +798,66 @@ pub struct AdvancedBuildStep {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub args: Option<BuildArgs>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub shm_size: Option<u64>,
+    pub shm_size: Option<ByteSize>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub target: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub network: Option<String>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub cache_from: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cache_to: Vec<String>,
     #[serde(default, skip_serializing_if = "Labels::is_empty")]
     pub labels: Labels,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh: Option<SshConfig>,
+    /// Build-time secrets, referencing top-level secrets
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secrets: Option<Secrets>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub platforms: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub additional_contexts: Option<AdditionalContexts>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_hosts: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub isolation: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub no_cache: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pull: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub privileged: bool,
+    #[serde(default, skip_serializing_if = "Ulimits::is_empty")]
+    pub ulimits: Ulimits,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub entitlements: Vec<String>,
+}
+
+/// SSH agent sockets or keys exposed to the build, as `ID[=path]` entries or
+/// a mapping of IDs to paths
+#[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(untagged)]
+pub enum SshConfig {
+    List(Vec<String>),
+    #[cfg(feature = "indexmap")]
+    Map(IndexMap<String, String>),
+    #[cfg(not(feature = "indexmap"))]
+    Map(HashMap<String, String>),
+}
+
+/// Named contexts available to the build, as `name=location` entries or a
+/// mapping
+#[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(untagged)]
+pub enum AdditionalContexts {
+    List(Vec<String>),
+    #[cfg(feature = "indexmap")]
+    Map(IndexMap<String, String>),
+    #[cfg(not(feature = "indexmap"))]
+    Map(HashMap<String, String>),
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
@@ -665,7 +878,7 @@ pub struct AdvancedNetworks(pub IndexMap<String, MapOrEmpty<AdvancedNetworkSetti
 #[derive(Clone, Default, Debug, Serialize, Deserialize, Eq, PartialEq)]
 pub struct AdvancedNetworks(pub HashMap<String, MapOrEmpty<AdvancedNetworkSettings>>);
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize, Eq, PartialEq, Hash)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Eq, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct AdvancedNetworkSettings {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -674,6 +887,25 @@ pub struct AdvancedNetworkSettings {
     pub ipv6_address: Option<String>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub aliases: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub link_local_ips: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mac_address: Option<String>,
+    /// The order in which the service is connected to its networks, higher
+    /// first
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i64>,
+    /// Which network's gateway is the default gateway, highest wins
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gw_priority: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interface_name: Option<String>,
+    #[cfg(feature = "indexmap")]
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub driver_opts: IndexMap<String, StringOrUnsigned>,
+    #[cfg(not(feature = "indexmap"))]
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub driver_opts: HashMap<String, StringOrUnsigned>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -753,17 +985,25 @@ impl ComposeNetworks {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
 #[serde(untagged)]
 pub enum ComposeNetwork {
     Detailed(ComposeNetworkSettingDetails),
     Bool(bool),
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
-#[serde(deny_unknown_fields)]
+/// The mapping form of `external`, with the deprecated `name` of the
+/// existing network
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Eq, PartialEq)]
 pub struct ComposeNetworkSettingDetails {
-    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[cfg(feature = "indexmap")]
+    #[serde(flatten, skip_serializing_if = "IndexMap::is_empty")]
+    pub extensions: IndexMap<Extension, Value>,
+    #[cfg(not(feature = "indexmap"))]
+    #[serde(flatten, skip_serializing_if = "HashMap::is_empty")]
+    pub extensions: HashMap<Extension, Value>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
@@ -783,6 +1023,9 @@ pub struct NetworkSettings {
     #[cfg(not(feature = "indexmap"))]
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub driver_opts: HashMap<String, Option<SingleValue>>,
+    /// Defaults to enabled when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enable_ipv4: Option<bool>,
     #[serde(default, skip_serializing_if = "std::ops::Not::not")]
     pub enable_ipv6: bool,
     #[serde(default, skip_serializing_if = "std::ops::Not::not")]
@@ -797,21 +1040,39 @@ pub struct NetworkSettings {
     pub name: Option<String>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Eq, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct Ipam {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub driver: Option<String>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub config: Vec<IpamConfig>,
+    /// Driver-specific options
+    #[cfg(feature = "indexmap")]
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub options: IndexMap<String, String>,
+    #[cfg(not(feature = "indexmap"))]
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub options: HashMap<String, String>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Eq, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct IpamConfig {
-    pub subnet: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subnet: Option<String>,
+    /// The range container addresses are allocated from, within `subnet`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ip_range: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub gateway: Option<String>,
+    /// Addresses reserved for use outside of compose, by host name
+    #[cfg(feature = "indexmap")]
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub aux_addresses: IndexMap<String, String>,
+    #[cfg(not(feature = "indexmap"))]
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub aux_addresses: HashMap<String, String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
@@ -821,11 +1082,15 @@ pub struct Deploy {
     pub mode: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub replicas: Option<i64>,
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    pub labels: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint_mode: Option<String>,
+    #[serde(default, skip_serializing_if = "Labels::is_empty")]
+    pub labels: Labels,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub update_config: Option<UpdateConfig>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub rollback_config: Option<UpdateConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub resources: Option<Resources>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub restart_policy: Option<RestartPolicy>,
@@ -843,15 +1108,15 @@ pub struct Healthcheck {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub test: Option<HealthcheckTest>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub interval: Option<String>,
+    pub interval: Option<ComposeDuration>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub timeout: Option<String>,
+    pub timeout: Option<ComposeDuration>,
     #[serde(default, skip_serializing_if = "is_zero")]
     pub retries: i64,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub start_period: Option<String>,
+    pub start_period: Option<ComposeDuration>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub start_interval: Option<String>,
+    pub start_interval: Option<ComposeDuration>,
     #[serde(default, skip_serializing_if = "std::ops::Not::not")]
     pub disable: bool,
 }
@@ -863,15 +1128,133 @@ pub enum HealthcheckTest {
     Multiple(Vec<String>),
 }
 
+/// The `develop` section used by `docker compose watch`
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Develop {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub watch: Vec<WatchRule>,
+}
+
+/// Describes what to do when files below `path` change
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct WatchRule {
+    pub path: String,
+    pub action: WatchAction,
+    /// Path in the container that `sync` actions copy to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub initial_sync: bool,
+    /// The command run by `sync+exec` actions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exec: Option<ServiceHook>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub enum WatchAction {
+    #[serde(rename = "sync")]
+    Sync,
+    #[serde(rename = "rebuild")]
+    Rebuild,
+    #[serde(rename = "restart")]
+    Restart,
+    #[serde(rename = "sync+restart")]
+    SyncRestart,
+    #[serde(rename = "sync+exec")]
+    SyncExec,
+}
+
+/// A command run inside a service's container, used by `post_start` and
+/// `pre_stop` hooks and by `sync+exec` watch rules
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ServiceHook {
+    pub command: Command,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub privileged: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<String>,
+    #[serde(default, skip_serializing_if = "Environment::is_empty")]
+    pub environment: Environment,
+}
+
+/// Block IO weights and limits of a service
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct BlkioConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<u16>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub weight_device: Vec<WeightDevice>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub device_read_bps: Vec<ThrottleDevice>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub device_read_iops: Vec<IopsDevice>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub device_write_bps: Vec<ThrottleDevice>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub device_write_iops: Vec<IopsDevice>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct WeightDevice {
+    pub path: String,
+    pub weight: u16,
+}
+
+/// A limit in bytes per second for a device
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ThrottleDevice {
+    pub path: String,
+    pub rate: ByteSize,
+}
+
+/// A limit in operations per second for a device
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct IopsDevice {
+    pub path: String,
+    pub rate: u64,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Default)]
 #[serde(deny_unknown_fields)]
 pub struct Limits {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cpus: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub memory: Option<String>,
+    pub memory: Option<ByteSize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pids: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub devices: Option<Vec<Device>>,
+    /// Swarm generic resources, only meaningful for reservations
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub generic_resources: Vec<GenericResource>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash, Default)]
+#[serde(deny_unknown_fields)]
+pub struct GenericResource {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub discrete_resource_spec: Option<DiscreteResourceSpec>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash, Default)]
+#[serde(deny_unknown_fields)]
+pub struct DiscreteResourceSpec {
+    pub kind: String,
+    pub value: i64,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Default)]
@@ -899,6 +1282,8 @@ pub struct Placement {
     pub constraints: Vec<String>,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub preferences: Vec<Preferences>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_replicas_per_node: Option<u64>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
@@ -922,52 +1307,68 @@ pub struct RestartPolicy {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub condition: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub delay: Option<String>,
+    pub delay: Option<ComposeDuration>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_attempts: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub window: Option<String>,
+    pub window: Option<ComposeDuration>,
 }
 
+/// How a service is updated, used for both `update_config` and
+/// `rollback_config`
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
 #[serde(deny_unknown_fields)]
 pub struct UpdateConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parallelism: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub delay: Option<String>,
+    pub delay: Option<ComposeDuration>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub failure_action: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub monitor: Option<String>,
+    pub monitor: Option<ComposeDuration>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_failure_ratio: Option<f64>,
+    /// `stop-first` or `start-first`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<String>,
 }
 
 #[cfg(feature = "indexmap")]
 #[derive(Clone, Default, Debug, Serialize, Deserialize, PartialEq)]
-pub struct ComposeSecrets(
-    #[serde(with = "serde_yaml::with::singleton_map_recursive")]
-    pub  IndexMap<String, Option<ComposeSecret>>,
-);
+pub struct ComposeSecrets(pub IndexMap<String, Option<ComposeSecret>>);
 
 #[cfg(not(feature = "indexmap"))]
 #[derive(Clone, Default, Debug, Serialize, Deserialize, PartialEq)]
-pub struct ComposeSecrets(
-    #[serde(with = "serde_yaml::with::singleton_map_recursive")]
-    pub  HashMap<String, Option<ComposeSecret>>,
-);
+pub struct ComposeSecrets(pub HashMap<String, Option<ComposeSecret>>);
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
-#[serde(deny_unknown_fields, rename_all = "snake_case")]
-pub enum ComposeSecret {
-    File(String),
-    Environment(String),
-    #[serde(untagged)]
-    External {
-        external: bool,
-        name: String,
-    },
+/// A top-level secret definition, its content comes from exactly one of
+/// `file`, `environment`, `content`, a `driver` or an `external` secret
+#[derive(Clone, Default, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ComposeSecret {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub external: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Labels::is_empty")]
+    pub labels: Labels,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub driver: Option<String>,
+    #[cfg(feature = "indexmap")]
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub driver_opts: IndexMap<String, Option<SingleValue>>,
+    #[cfg(not(feature = "indexmap"))]
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub driver_opts: HashMap<String, Option<SingleValue>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template_driver: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
@@ -1006,6 +1407,71 @@ pub struct AdvancedSecrets {
     pub mode: Option<String>,
 }
 
+#[cfg(feature = "indexmap")]
+#[derive(Clone, Default, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ComposeConfigs(pub IndexMap<String, Option<ComposeConfig>>);
+
+#[cfg(not(feature = "indexmap"))]
+#[derive(Clone, Default, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ComposeConfigs(pub HashMap<String, Option<ComposeConfig>>);
+
+/// A top-level config definition, its content comes from exactly one of
+/// `file`, `environment`, `content` or an `external` config
+#[derive(Clone, Default, Debug, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ComposeConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub external: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Labels::is_empty")]
+    pub labels: Labels,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template_driver: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
+#[serde(untagged)]
+pub enum Configs {
+    Simple(Vec<String>),
+    Advanced(Vec<AdvancedConfigs>),
+}
+
+impl Default for Configs {
+    fn default() -> Self {
+        Self::Simple(Vec::new())
+    }
+}
+
+impl Configs {
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Self::Simple(v) => v.is_empty(),
+            Self::Advanced(v) => v.is_empty(),
+        }
+    }
+}
+
+#[derive(Clone, Default, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
+#[serde(deny_unknown_fields)]
+pub struct AdvancedConfigs {
+    pub source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
 #[serde(rename_all = "lowercase")]
 pub enum PullPolicy {
@@ -1061,7 +1527,7 @@ pub struct Volume {
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash, Default)]
 #[serde(deny_unknown_fields)]
 pub struct TmpfsSettings {
-    pub size: u64,
+    pub size: ByteSize,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
@@ -1107,19 +1573,14 @@ pub enum Group {
     Gid(u32),
 }
 
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Hash)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Hash, Default)]
 #[serde(untagged)]
 pub enum MapOrEmpty<T> {
     Map(T),
+    #[default]
     Empty,
 }
 
-impl<T> Default for MapOrEmpty<T> {
-    fn default() -> Self {
-        Self::Empty
-    }
-}
-
 impl<T> From<MapOrEmpty<T>> for Option<T> {
     fn from(value: MapOrEmpty<T>) -> Self {
         match value {
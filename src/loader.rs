@@ -0,0 +1,437 @@
+use std::fmt;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+#[cfg(feature = "norway")]
+use serde_norway as serde_yaml;
+#[cfg(feature = "yml")]
+use serde_yml as serde_yaml;
+
+use serde_yaml::Value;
+
+use crate::dotenv::{Dotenv, DotenvError};
+use crate::interpolation::{interpolate_value, InterpolationError, ProcessEnv, VariableSource};
+use crate::{include, Compose, ComposeFile, Services};
+
+/// Reads one or more compose files from disk and resolves them against a
+/// project directory.
+///
+/// Variables referenced as `${VAR}` are interpolated from the process
+/// environment unless another source is given with
+/// [`variables`](Self::variables), falling back to the `.env` file of the
+/// project directory (or the files given with [`env_file`](Self::env_file)).
+///
+/// ```no_run
+/// use docker_compose_types::ComposeLoader;
+///
+/// let project = ComposeLoader::new()
+///     .file("compose.yml")
+///     .file("compose.override.yml")
+///     .load()
+///     .unwrap();
+/// println!("{:?}", project.project_directory);
+/// ```
+#[derive(Clone, Default)]
+pub struct ComposeLoader {
+    files: Vec<PathBuf>,
+    project_directory: Option<PathBuf>,
+    env_files: Vec<PathBuf>,
+    variables: Option<Arc<dyn VariableSource + Send + Sync>>,
+    skip_interpolation: bool,
+}
+
+impl fmt::Debug for ComposeLoader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ComposeLoader")
+            .field("files", &self.files)
+            .field("project_directory", &self.project_directory)
+            .field("env_files", &self.env_files)
+            .field("skip_interpolation", &self.skip_interpolation)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ComposeLoader {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds a compose file, later files override earlier ones.
+    pub fn file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.files.push(path.into());
+        self
+    }
+
+    pub fn files<I, P>(mut self, paths: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        self.files.extend(paths.into_iter().map(Into::into));
+        self
+    }
+
+    /// Sets the directory relative paths are resolved against. Defaults to the
+    /// parent directory of the first compose file.
+    pub fn project_directory(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.project_directory = Some(dir.into());
+        self
+    }
+
+    /// Adds an environment file read instead of the project's `.env`, like
+    /// `docker compose --env-file`. Later files override earlier ones.
+    pub fn env_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.env_files.push(path.into());
+        self
+    }
+
+    /// Sets the source `${VAR}` references are interpolated from
+    pub fn variables(mut self, variables: impl VariableSource + Send + Sync + 'static) -> Self {
+        self.variables = Some(Arc::new(variables));
+        self
+    }
+
+    /// Enables or disables variable interpolation, enabled by default
+    pub fn interpolate(mut self, enabled: bool) -> Self {
+        self.skip_interpolation = !enabled;
+        self
+    }
+
+    pub fn load(&self) -> Result<Project, LoadError> {
+        let first = self.files.first().ok_or(LoadError::NoFiles)?;
+        let files = self
+            .files
+            .iter()
+            .map(|f| absolute_path(f))
+            .collect::<Result<Vec<_>, _>>()?;
+        let project_directory = match &self.project_directory {
+            Some(dir) => absolute_path(dir)?,
+            None => absolute_path(first)?
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default(),
+        };
+
+        let lookup: &dyn VariableSource = match &self.variables {
+            Some(variables) => variables.as_ref(),
+            None => &ProcessEnv,
+        };
+        let env_files = if self.env_files.is_empty() {
+            let default = project_directory.join(".env");
+            default.is_file().then_some(default).into_iter().collect()
+        } else {
+            self.env_files
+                .iter()
+                .map(|f| absolute_path(f))
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        let dotenv = read_env_files(&env_files, lookup)?;
+
+        // Variables from the environment take precedence over the env files
+        let variables = (lookup, &dotenv);
+        let variables: Option<&dyn VariableSource> =
+            (!self.skip_interpolation).then_some(&variables);
+        let compose = load_files(&files, &project_directory, variables, &mut Vec::new())?;
+        Ok(Project {
+            compose,
+            project_directory,
+            files,
+            dotenv,
+        })
+    }
+}
+
+/// A [`Compose`] model together with the context it was loaded in.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Project {
+    pub compose: Compose,
+    /// Absolute directory relative paths in the model are resolved against.
+    pub project_directory: PathBuf,
+    /// Absolute paths of the compose files, in the order they were applied.
+    pub files: Vec<PathBuf>,
+    /// Variables of the project's `.env` file or the env files given to the
+    /// loader
+    pub dotenv: Dotenv,
+}
+
+impl Project {
+    /// Resolves `path` against the project directory, leaving absolute paths
+    /// untouched.
+    pub fn resolve_path(&self, path: impl AsRef<Path>) -> PathBuf {
+        clean_path(&self.project_directory.join(path))
+    }
+}
+
+impl Compose {
+    /// Loads the given compose files, using the directory of the first file as
+    /// the project directory. Use [`ComposeLoader`] for more control.
+    pub fn load<P: AsRef<Path>>(paths: &[P]) -> Result<Project, LoadError> {
+        ComposeLoader::new()
+            .files(paths.iter().map(|p| p.as_ref().to_path_buf()))
+            .load()
+    }
+}
+
+impl From<ComposeFile> for Compose {
+    fn from(file: ComposeFile) -> Self {
+        match file {
+            ComposeFile::V2Plus(compose) => compose,
+            ComposeFile::V1(services) => Compose {
+                services: Services(services.into_iter().map(|(k, v)| (k, Some(v))).collect()),
+                ..Default::default()
+            },
+            ComposeFile::Single(single) => Compose {
+                service: Some(single.service),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// The error returned when loading a compose project fails
+#[derive(Debug)]
+pub enum LoadError {
+    /// No compose file was given to the loader
+    NoFiles,
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        source: serde_yaml::Error,
+    },
+    /// A file ends up including itself, the first and last entries are the same
+    /// file
+    IncludeCycle(Vec<PathBuf>),
+    /// An included file defines a resource that already exists with a
+    /// different definition
+    IncludeConflict {
+        path: PathBuf,
+        kind: &'static str,
+        name: String,
+    },
+    /// A service ends up extending itself, the first and last entries are the
+    /// same service
+    ExtendsCycle(Vec<(PathBuf, String)>),
+    /// An `extends` entry does not name the service to extend
+    InvalidExtends {
+        path: PathBuf,
+        service: String,
+    },
+    /// A referenced service does not exist in the given file
+    UnknownService {
+        path: PathBuf,
+        service: String,
+    },
+    Interpolation {
+        path: PathBuf,
+        source: InterpolationError,
+    },
+    /// An environment file contains lines that could not be parsed
+    EnvFile {
+        path: PathBuf,
+        errors: Vec<DotenvError>,
+    },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoFiles => f.write_str("no compose file given"),
+            Self::Io { path, source } => write!(f, "failed to read {}: {source}", path.display()),
+            Self::Parse { path, source } => {
+                write!(f, "failed to parse {}: {source}", path.display())
+            }
+            Self::IncludeCycle(files) => {
+                f.write_str("include cycle detected: ")?;
+                for (i, file) in files.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(" -> ")?;
+                    }
+                    write!(f, "{}", file.display())?;
+                }
+                Ok(())
+            }
+            Self::IncludeConflict { path, kind, name } => write!(
+                f,
+                "{kind}.{name} included from {} conflicts with an existing definition",
+                path.display()
+            ),
+            Self::ExtendsCycle(services) => {
+                f.write_str("extends cycle detected: ")?;
+                for (i, (path, service)) in services.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(" -> ")?;
+                    }
+                    write!(f, "{service} ({})", path.display())?;
+                }
+                Ok(())
+            }
+            Self::InvalidExtends { path, service } => write!(
+                f,
+                "extends of service {service} in {} does not name a service",
+                path.display()
+            ),
+            Self::UnknownService { path, service } => {
+                write!(f, "service {service} not found in {}", path.display())
+            }
+            Self::Interpolation { path, source } => {
+                write!(f, "failed to interpolate {}: {source}", path.display())
+            }
+            Self::EnvFile { path, errors } => {
+                write!(f, "failed to parse {}", path.display())?;
+                for error in errors {
+                    write!(f, "\n  {error}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::NoFiles
+            | Self::IncludeCycle(_)
+            | Self::IncludeConflict { .. }
+            | Self::ExtendsCycle(_)
+            | Self::InvalidExtends { .. }
+            | Self::UnknownService { .. }
+            | Self::EnvFile { .. } => None,
+            Self::Io { source, .. } => Some(source),
+            Self::Parse { source, .. } => Some(source),
+            Self::Interpolation { source, .. } => Some(source),
+        }
+    }
+}
+
+/// Reads `files` in order and applies each one on top of the previous ones.
+/// Includes are resolved per file against `working_dir`, `stack` holds the
+/// files that are currently being loaded. Files are interpolated with
+/// `variables` when given.
+pub(crate) fn load_files(
+    files: &[PathBuf],
+    working_dir: &Path,
+    variables: Option<&dyn VariableSource>,
+    stack: &mut Vec<PathBuf>,
+) -> Result<Compose, LoadError> {
+    let mut compose: Option<Compose> = None;
+    for file in files {
+        if stack.contains(file) {
+            let mut cycle = stack.clone();
+            cycle.push(file.clone());
+            return Err(LoadError::IncludeCycle(cycle));
+        }
+        stack.push(file.clone());
+        let mut loaded = read_compose_file(file, variables)?;
+        loaded.resolve_extends_with(file, variables)?;
+        include::resolve_includes(&mut loaded, working_dir, variables, stack)?;
+        stack.pop();
+
+        compose = Some(match compose {
+            Some(base) => base.merge(loaded),
+            None => loaded,
+        });
+    }
+    Ok(compose.unwrap_or_default())
+}
+
+/// Reads `paths` in order into a single [`Dotenv`], resolving bare variables
+/// and references from `lookup`
+pub(crate) fn read_env_files(
+    paths: &[PathBuf],
+    lookup: &dyn VariableSource,
+) -> Result<Dotenv, LoadError> {
+    let mut dotenv = Dotenv::default();
+    for path in paths {
+        let lookup = (lookup, &dotenv);
+        let parsed = Dotenv::read(path, &lookup).map_err(|source| LoadError::Io {
+            path: path.clone(),
+            source,
+        })?;
+        if !parsed.errors.is_empty() {
+            return Err(LoadError::EnvFile {
+                path: path.clone(),
+                errors: parsed.errors,
+            });
+        }
+        let vars: Vec<_> = parsed
+            .vars
+            .into_iter()
+            .map(|(name, value)| {
+                let value = value.or_else(|| lookup.var(&name));
+                (name, value)
+            })
+            .collect();
+        dotenv.vars.extend(vars);
+    }
+    Ok(dotenv)
+}
+
+pub(crate) fn read_compose_file(
+    path: &Path,
+    variables: Option<&dyn VariableSource>,
+) -> Result<Compose, LoadError> {
+    let content = std::fs::read_to_string(path).map_err(|source| LoadError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let parse_error = |source| LoadError::Parse {
+        path: path.to_path_buf(),
+        source,
+    };
+    let mut value: Value = serde_yaml::from_str(&content).map_err(parse_error)?;
+    value.apply_merge().map_err(parse_error)?;
+    if let Some(variables) = variables {
+        interpolate_value(&mut value, variables).map_err(|source| LoadError::Interpolation {
+            path: path.to_path_buf(),
+            source,
+        })?;
+    }
+    compose_from_value(value).map_err(parse_error)
+}
+
+pub(crate) fn compose_from_value(value: Value) -> Result<Compose, serde_yaml::Error> {
+    if value.is_null() {
+        return Ok(Compose::default());
+    }
+    match serde_yaml::from_value::<ComposeFile>(value.clone()) {
+        Ok(file) => Ok(file.into()),
+        // The untagged ComposeFile swallows meaningful errors, so re-parse as
+        // Compose to report why the file was rejected
+        Err(_) => serde_yaml::from_value::<Compose>(value),
+    }
+}
+
+fn absolute_path(path: &Path) -> Result<PathBuf, LoadError> {
+    if path.is_absolute() {
+        return Ok(clean_path(path));
+    }
+    let cwd = std::env::current_dir().map_err(|source| LoadError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    Ok(clean_path(&cwd.join(path)))
+}
+
+/// Lexically removes `.` and `..` components without touching the filesystem
+pub(crate) fn clean_path(path: &Path) -> PathBuf {
+    let mut cleaned = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match cleaned.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    cleaned.pop();
+                }
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => cleaned.push(component),
+            },
+            other => cleaned.push(other),
+        }
+    }
+    cleaned
+}
@@ -0,0 +1,908 @@
+use std::hash::Hash;
+
+use crate::{
+    AdditionalContexts, AdvancedBuildStep, AdvancedConfigs, AdvancedNetworkSettings,
+    AdvancedSecrets, BlkioConfig, BuildArgs, BuildStep, Compose, ComposeVolume, Configs,
+    DependsCondition, DependsOnOptions, Deploy, Environment, Healthcheck, Includes, Ipam, Labels,
+    Limits, LoggingParameters, Map, MapOrEmpty, NetworkSettings, Networks, Placement, Port, Ports,
+    Resources, RestartPolicy, Secrets, Service, SingleValue, SshConfig, StringOrList, SysCtls,
+    Tmpfs, UpdateConfig, Volumes,
+};
+
+impl Compose {
+    /// Applies `other` on top of `self` the way `docker compose -f base.yml -f
+    /// override.yml` does. Services, networks and volumes with the same name
+    /// are merged with each other, see [`Service::merge`] for the rules. The
+    /// IPAM `config` entries of a network are appended to each other.
+    pub fn merge(mut self, other: Compose) -> Compose {
+        let Compose {
+            version,
+            name,
+            includes,
+            services,
+            volumes,
+            networks,
+            service,
+            secrets,
+            configs,
+            extensions,
+        } = other;
+
+        self.version = version.or(self.version);
+        self.name = name.or(self.name);
+        self.includes = match (self.includes, includes) {
+            (Some(Includes::Short(mut base)), Some(Includes::Short(other))) => {
+                base.extend(other);
+                Some(Includes::Short(base))
+            }
+            (Some(base), Some(other)) => {
+                let mut merged = base.into_long();
+                merged.extend(other.into_long());
+                Some(Includes::Long(merged))
+            }
+            (base, other) => other.or(base),
+        };
+        for (name, service) in services.0 {
+            let merged = match (
+                self.services.0.get_mut(&name).and_then(Option::take),
+                service,
+            ) {
+                (Some(base), Some(other)) => Some(base.merge(other)),
+                (base, other) => other.or(base),
+            };
+            self.services.0.insert(name, merged);
+        }
+        merge_entries(&mut self.volumes.0, volumes.0, merge_volume);
+        merge_entries(&mut self.networks.0, networks.0, merge_network);
+        self.service = match (self.service, service) {
+            (Some(base), Some(other)) => Some(base.merge(other)),
+            (base, other) => other.or(base),
+        };
+        self.secrets = match (self.secrets, secrets) {
+            (Some(mut base), Some(other)) => {
+                merge_map(&mut base.0, other.0);
+                Some(base)
+            }
+            (base, other) => other.or(base),
+        };
+        self.configs = match (self.configs, configs) {
+            (Some(mut base), Some(other)) => {
+                merge_map(&mut base.0, other.0);
+                Some(base)
+            }
+            (base, other) => other.or(base),
+        };
+        merge_map(&mut self.extensions, extensions);
+        self
+    }
+}
+
+impl Service {
+    /// Applies `other` on top of `self` following the compose-spec merge rules:
+    /// scalars are replaced, mappings such as `environment`, `labels` and
+    /// `sysctls` are merged key-wise, sequences such as `ports`, `expose` and
+    /// `dns` are appended without duplicates and `volumes`, `secrets` and
+    /// `configs` are merged by their target. `command` and `entrypoint` are replaced.
+    ///
+    /// When the two sides use different syntaxes (e.g. a list and a map of
+    /// environment variables) they are reconciled before merging.
+    pub fn merge(mut self, other: Service) -> Service {
+        // Destructure exhaustively so new fields can't be forgotten here
+        let Service {
+            hostname,
+            domainname,
+            mac_address,
+            privileged,
+            read_only,
+            healthcheck,
+            deploy,
+            image,
+            container_name,
+            build_,
+            pid,
+            ports,
+            environment,
+            network_mode,
+            devices,
+            restart,
+            labels,
+            tmpfs,
+            ulimits,
+            volumes,
+            networks,
+            cap_add,
+            cap_drop,
+            depends_on,
+            command,
+            entrypoint,
+            env_file,
+            stop_grace_period,
+            profiles,
+            links,
+            dns,
+            dns_opt,
+            ipc,
+            net,
+            stop_signal,
+            user,
+            userns_mode,
+            working_dir,
+            expose,
+            volumes_from,
+            extends,
+            logging,
+            scale,
+            init,
+            stdin_open,
+            shm_size,
+            extensions,
+            extra_hosts,
+            group_add,
+            tty,
+            sysctls,
+            security_opt,
+            secrets,
+            configs,
+            pull_policy,
+            cgroup_parent,
+            mem_limit,
+            mem_reservation,
+            mem_swappiness,
+            memswap_limit,
+            oom_kill_disable,
+            oom_score_adj,
+            pids_limit,
+            cpus,
+            cpu_shares,
+            cpu_quota,
+            cpu_period,
+            cpuset,
+            cpu_count,
+            cpu_percent,
+            cpu_rt_runtime,
+            cpu_rt_period,
+            blkio_config,
+            runtime,
+            develop,
+            post_start,
+            pre_stop,
+        } = other;
+
+        self.hostname = hostname.or(self.hostname);
+        self.domainname = domainname.or(self.domainname);
+        self.mac_address = mac_address.or(self.mac_address);
+        self.privileged |= privileged;
+        self.read_only |= read_only;
+        self.healthcheck = match (self.healthcheck, healthcheck) {
+            (Some(base), Some(other)) => Some(merge_healthcheck(base, other)),
+            (base, other) => other.or(base),
+        };
+        self.deploy = match (self.deploy, deploy) {
+            (Some(base), Some(other)) => Some(merge_deploy(base, other)),
+            (base, other) => other.or(base),
+        };
+        self.image = image.or(self.image);
+        self.container_name = container_name.or(self.container_name);
+        self.build_ = match (self.build_, build_) {
+            (Some(base), Some(other)) => Some(merge_build(base, other)),
+            (base, other) => other.or(base),
+        };
+        self.pid = pid.or(self.pid);
+        self.ports = merge_ports(self.ports, ports);
+        self.environment = merge_environment(self.environment, environment);
+        self.network_mode = network_mode.or(self.network_mode);
+        append_unique(&mut self.devices, devices);
+        self.restart = restart.or(self.restart);
+        self.labels = merge_labels(self.labels, labels);
+        self.tmpfs = match (self.tmpfs, tmpfs) {
+            (Some(base), Some(other)) => {
+                let mut merged = tmpfs_to_vec(base);
+                append_unique(&mut merged, tmpfs_to_vec(other));
+                Some(Tmpfs::List(merged))
+            }
+            (base, other) => other.or(base),
+        };
+        merge_map(&mut self.ulimits.0, ulimits.0);
+        self.volumes = merge_volumes(self.volumes, volumes);
+        self.networks = merge_networks(self.networks, networks);
+        append_unique(&mut self.cap_add, cap_add);
+        append_unique(&mut self.cap_drop, cap_drop);
+        self.depends_on = merge_depends_on(self.depends_on, depends_on);
+        self.command = command.or(self.command);
+        self.entrypoint = entrypoint.or(self.entrypoint);
+        self.env_file = match (self.env_file, env_file) {
+            (Some(base), Some(other)) => {
+                let mut merged = string_or_list_to_vec(base);
+                append_unique(&mut merged, string_or_list_to_vec(other));
+                Some(StringOrList::List(merged))
+            }
+            (base, other) => other.or(base),
+        };
+        self.stop_grace_period = stop_grace_period.or(self.stop_grace_period);
+        append_unique(&mut self.profiles, profiles);
+        append_unique(&mut self.links, links);
+        append_unique(&mut self.dns, dns);
+        append_unique(&mut self.dns_opt, dns_opt);
+        self.ipc = ipc.or(self.ipc);
+        self.net = net.or(self.net);
+        self.stop_signal = stop_signal.or(self.stop_signal);
+        self.user = user.or(self.user);
+        self.userns_mode = userns_mode.or(self.userns_mode);
+        self.working_dir = working_dir.or(self.working_dir);
+        append_unique(&mut self.expose, expose);
+        append_unique(&mut self.volumes_from, volumes_from);
+        if !extends.is_empty() {
+            self.extends = extends;
+        }
+        self.logging = match (self.logging, logging) {
+            (Some(base), Some(other)) => Some(merge_logging(base, other)),
+            (base, other) => other.or(base),
+        };
+        if scale != 0 {
+            self.scale = scale;
+        }
+        self.init |= init;
+        self.stdin_open |= stdin_open;
+        self.shm_size = shm_size.or(self.shm_size);
+        merge_map(&mut self.extensions, extensions);
+        append_unique(&mut self.extra_hosts, extra_hosts);
+        append_unique(&mut self.group_add, group_add);
+        self.tty |= tty;
+        self.sysctls = merge_sysctls(self.sysctls, sysctls);
+        append_unique(&mut self.security_opt, security_opt);
+        self.secrets = match (self.secrets, secrets) {
+            (Some(base), Some(other)) => Some(merge_secrets(base, other)),
+            (base, other) => other.or(base),
+        };
+        self.configs = match (self.configs, configs) {
+            (Some(base), Some(other)) => Some(merge_configs(base, other)),
+            (base, other) => other.or(base),
+        };
+        self.pull_policy = pull_policy.or(self.pull_policy);
+        self.cgroup_parent = cgroup_parent.or(self.cgroup_parent);
+        self.mem_limit = mem_limit.or(self.mem_limit);
+        self.mem_reservation = mem_reservation.or(self.mem_reservation);
+        self.mem_swappiness = mem_swappiness.or(self.mem_swappiness);
+        self.memswap_limit = memswap_limit.or(self.memswap_limit);
+        self.oom_kill_disable |= oom_kill_disable;
+        self.oom_score_adj = oom_score_adj.or(self.oom_score_adj);
+        self.pids_limit = pids_limit.or(self.pids_limit);
+        self.cpus = cpus.or(self.cpus);
+        self.cpu_shares = cpu_shares.or(self.cpu_shares);
+        self.cpu_quota = cpu_quota.or(self.cpu_quota);
+        self.cpu_period = cpu_period.or(self.cpu_period);
+        self.cpuset = cpuset.or(self.cpuset);
+        self.cpu_count = cpu_count.or(self.cpu_count);
+        self.cpu_percent = cpu_percent.or(self.cpu_percent);
+        self.cpu_rt_runtime = cpu_rt_runtime.or(self.cpu_rt_runtime);
+        self.cpu_rt_period = cpu_rt_period.or(self.cpu_rt_period);
+        self.blkio_config = match (self.blkio_config, blkio_config) {
+            (Some(base), Some(other)) => Some(merge_blkio_config(base, other)),
+            (base, other) => other.or(base),
+        };
+        self.runtime = runtime.or(self.runtime);
+        self.develop = develop.or(self.develop);
+        self.post_start.extend(post_start);
+        self.pre_stop.extend(pre_stop);
+        self
+    }
+}
+
+fn append_unique<T: PartialEq>(base: &mut Vec<T>, other: Vec<T>) {
+    for item in other {
+        if !base.contains(&item) {
+            base.push(item);
+        }
+    }
+}
+
+fn merge_map<K: Eq + Hash, V>(base: &mut Map<K, V>, other: Map<K, V>) {
+    for (key, value) in other {
+        base.insert(key, value);
+    }
+}
+
+/// Merges `other` into `base`, entries present on both sides are combined with
+/// `merge`
+fn merge_entries<T>(
+    base: &mut Map<String, MapOrEmpty<T>>,
+    other: Map<String, MapOrEmpty<T>>,
+    merge: fn(T, T) -> T,
+) {
+    for (name, value) in other {
+        let merged = match (base.get_mut(&name).map(std::mem::take), value) {
+            (Some(MapOrEmpty::Map(base)), MapOrEmpty::Map(other)) => {
+                MapOrEmpty::Map(merge(base, other))
+            }
+            (Some(base), MapOrEmpty::Empty) => base,
+            (_, other) => other,
+        };
+        base.insert(name, merged);
+    }
+}
+
+fn merge_volume(mut base: ComposeVolume, other: ComposeVolume) -> ComposeVolume {
+    merge_map(&mut base.driver_opts, other.driver_opts);
+    ComposeVolume {
+        driver: other.driver.or(base.driver),
+        driver_opts: base.driver_opts,
+        external: other.external.or(base.external),
+        labels: merge_labels(base.labels, other.labels),
+        name: other.name.or(base.name),
+    }
+}
+
+fn merge_network(mut base: NetworkSettings, other: NetworkSettings) -> NetworkSettings {
+    merge_map(&mut base.driver_opts, other.driver_opts);
+    NetworkSettings {
+        attachable: base.attachable || other.attachable,
+        driver: other.driver.or(base.driver),
+        driver_opts: base.driver_opts,
+        enable_ipv4: other.enable_ipv4.or(base.enable_ipv4),
+        enable_ipv6: base.enable_ipv6 || other.enable_ipv6,
+        internal: base.internal || other.internal,
+        external: other.external.or(base.external),
+        ipam: merge_option(base.ipam, other.ipam, merge_ipam),
+        labels: merge_labels(base.labels, other.labels),
+        name: other.name.or(base.name),
+    }
+}
+
+/// `config` entries are appended, skipping duplicates
+fn merge_ipam(mut base: Ipam, other: Ipam) -> Ipam {
+    append_unique(&mut base.config, other.config);
+    merge_map(&mut base.options, other.options);
+    Ipam {
+        driver: other.driver.or(base.driver),
+        config: base.config,
+        options: base.options,
+    }
+}
+
+fn string_or_list_to_vec(value: StringOrList) -> Vec<String> {
+    match value {
+        StringOrList::Simple(s) => vec![s],
+        StringOrList::List(list) => list,
+    }
+}
+
+fn tmpfs_to_vec(value: Tmpfs) -> Vec<String> {
+    match value {
+        Tmpfs::Simple(s) => vec![s],
+        Tmpfs::List(list) => list,
+    }
+}
+
+fn merge_healthcheck(base: Healthcheck, other: Healthcheck) -> Healthcheck {
+    Healthcheck {
+        test: other.test.or(base.test),
+        interval: other.interval.or(base.interval),
+        timeout: other.timeout.or(base.timeout),
+        retries: if other.retries != 0 {
+            other.retries
+        } else {
+            base.retries
+        },
+        start_period: other.start_period.or(base.start_period),
+        start_interval: other.start_interval.or(base.start_interval),
+        disable: base.disable || other.disable,
+    }
+}
+
+/// Merges two optional mappings with `merge` when both are set
+fn merge_option<T>(base: Option<T>, other: Option<T>, merge: fn(T, T) -> T) -> Option<T> {
+    match (base, other) {
+        (Some(base), Some(other)) => Some(merge(base, other)),
+        (base, other) => other.or(base),
+    }
+}
+
+fn merge_deploy(base: Deploy, other: Deploy) -> Deploy {
+    Deploy {
+        mode: other.mode.or(base.mode),
+        replicas: other.replicas.or(base.replicas),
+        endpoint_mode: other.endpoint_mode.or(base.endpoint_mode),
+        labels: merge_labels(base.labels, other.labels),
+        update_config: merge_option(base.update_config, other.update_config, merge_update_config),
+        rollback_config: merge_option(
+            base.rollback_config,
+            other.rollback_config,
+            merge_update_config,
+        ),
+        resources: merge_option(base.resources, other.resources, merge_resources),
+        restart_policy: merge_option(
+            base.restart_policy,
+            other.restart_policy,
+            merge_restart_policy,
+        ),
+        placement: merge_option(base.placement, other.placement, merge_placement),
+    }
+}
+
+fn merge_update_config(base: UpdateConfig, other: UpdateConfig) -> UpdateConfig {
+    UpdateConfig {
+        parallelism: other.parallelism.or(base.parallelism),
+        delay: other.delay.or(base.delay),
+        failure_action: other.failure_action.or(base.failure_action),
+        monitor: other.monitor.or(base.monitor),
+        max_failure_ratio: other.max_failure_ratio.or(base.max_failure_ratio),
+        order: other.order.or(base.order),
+    }
+}
+
+fn merge_resources(base: Resources, other: Resources) -> Resources {
+    Resources {
+        limits: merge_option(base.limits, other.limits, merge_limits),
+        reservations: merge_option(base.reservations, other.reservations, merge_limits),
+    }
+}
+
+fn merge_limits(mut base: Limits, other: Limits) -> Limits {
+    append_unique(&mut base.generic_resources, other.generic_resources);
+    Limits {
+        cpus: other.cpus.or(base.cpus),
+        memory: other.memory.or(base.memory),
+        pids: other.pids.or(base.pids),
+        devices: match (base.devices, other.devices) {
+            (Some(mut base), Some(other)) => {
+                append_unique(&mut base, other);
+                Some(base)
+            }
+            (base, other) => other.or(base),
+        },
+        generic_resources: base.generic_resources,
+    }
+}
+
+fn merge_restart_policy(base: RestartPolicy, other: RestartPolicy) -> RestartPolicy {
+    RestartPolicy {
+        condition: other.condition.or(base.condition),
+        delay: other.delay.or(base.delay),
+        max_attempts: other.max_attempts.or(base.max_attempts),
+        window: other.window.or(base.window),
+    }
+}
+
+fn merge_placement(mut base: Placement, other: Placement) -> Placement {
+    append_unique(&mut base.constraints, other.constraints);
+    append_unique(&mut base.preferences, other.preferences);
+    Placement {
+        constraints: base.constraints,
+        preferences: base.preferences,
+        max_replicas_per_node: other.max_replicas_per_node.or(base.max_replicas_per_node),
+    }
+}
+
+fn merge_logging(base: LoggingParameters, other: LoggingParameters) -> LoggingParameters {
+    // Options only carry over when the driver stays the same
+    let same_driver = other.driver.is_none() || other.driver == base.driver;
+    let options = match (base.options, other.options) {
+        (Some(mut base), Some(other)) if same_driver => {
+            merge_map(&mut base, other);
+            Some(base)
+        }
+        (base, other) if same_driver => other.or(base),
+        (_, other) => other,
+    };
+    LoggingParameters {
+        driver: other.driver.or(base.driver),
+        options,
+    }
+}
+
+fn merge_build(base: BuildStep, other: BuildStep) -> BuildStep {
+    match (base, other) {
+        (BuildStep::Advanced(mut base), BuildStep::Simple(context)) => {
+            base.context = context;
+            BuildStep::Advanced(base)
+        }
+        (BuildStep::Simple(context), BuildStep::Advanced(mut other)) => {
+            if other.context.is_empty() {
+                other.context = context;
+            }
+            BuildStep::Advanced(other)
+        }
+        (BuildStep::Advanced(base), BuildStep::Advanced(other)) => {
+            BuildStep::Advanced(merge_advanced_build(base, other))
+        }
+        (_, other) => other,
+    }
+}
+
+fn merge_advanced_build(base: AdvancedBuildStep, other: AdvancedBuildStep) -> AdvancedBuildStep {
+    let mut cache_from = base.cache_from;
+    append_unique(&mut cache_from, other.cache_from);
+    let mut cache_to = base.cache_to;
+    append_unique(&mut cache_to, other.cache_to);
+    let mut tags = base.tags;
+    append_unique(&mut tags, other.tags);
+    let mut platforms = base.platforms;
+    append_unique(&mut platforms, other.platforms);
+    let mut extra_hosts = base.extra_hosts;
+    append_unique(&mut extra_hosts, other.extra_hosts);
+    let mut entitlements = base.entitlements;
+    append_unique(&mut entitlements, other.entitlements);
+    let mut ulimits = base.ulimits;
+    merge_map(&mut ulimits.0, other.ulimits.0);
+    AdvancedBuildStep {
+        context: if other.context.is_empty() {
+            base.context
+        } else {
+            other.context
+        },
+        dockerfile: other.dockerfile.or(base.dockerfile),
+        dockerfile_inline: other.dockerfile_inline.or(base.dockerfile_inline),
+        args: match (base.args, other.args) {
+            (Some(base), Some(other)) => {
+                let mut merged = build_args_to_map(base);
+                merge_map(&mut merged, build_args_to_map(other));
+                Some(BuildArgs::KvPair(merged))
+            }
+            (base, other) => other.or(base),
+        },
+        shm_size: other.shm_size.or(base.shm_size),
+        target: other.target.or(base.target),
+        network: other.network.or(base.network),
+        cache_from,
+        cache_to,
+        labels: merge_labels(base.labels, other.labels),
+        ssh: match (base.ssh, other.ssh) {
+            (Some(SshConfig::List(mut base)), Some(SshConfig::List(other))) => {
+                append_unique(&mut base, other);
+                Some(SshConfig::List(base))
+            }
+            (base, other) => other.or(base),
+        },
+        secrets: match (base.secrets, other.secrets) {
+            (Some(base), Some(other)) => Some(merge_secrets(base, other)),
+            (base, other) => other.or(base),
+        },
+        tags,
+        platforms,
+        additional_contexts: match (base.additional_contexts, other.additional_contexts) {
+            (Some(base), Some(other)) => {
+                let mut merged = additional_contexts_to_map(base);
+                merge_map(&mut merged, additional_contexts_to_map(other));
+                Some(AdditionalContexts::Map(merged))
+            }
+            (base, other) => other.or(base),
+        },
+        extra_hosts,
+        isolation: other.isolation.or(base.isolation),
+        no_cache: base.no_cache || other.no_cache,
+        pull: base.pull || other.pull,
+        privileged: base.privileged || other.privileged,
+        ulimits,
+        entitlements,
+    }
+}
+
+fn additional_contexts_to_map(contexts: AdditionalContexts) -> Map<String, String> {
+    match contexts {
+        AdditionalContexts::List(list) => split_key_values(list),
+        AdditionalContexts::Map(map) => map,
+    }
+}
+
+fn build_args_to_map(args: BuildArgs) -> Map<String, String> {
+    match args {
+        BuildArgs::Simple(arg) => split_key_values(vec![arg]),
+        BuildArgs::List(args) => split_key_values(args),
+        BuildArgs::KvPair(map) => map,
+    }
+}
+
+/// Splits `KEY=VALUE` entries, a bare `KEY` maps to an empty value
+fn split_key_values(entries: Vec<String>) -> Map<String, String> {
+    entries
+        .into_iter()
+        .map(|entry| match entry.split_once('=') {
+            Some((key, value)) => (key.to_string(), value.to_string()),
+            None => (entry, String::new()),
+        })
+        .collect()
+}
+
+fn merge_labels(base: Labels, other: Labels) -> Labels {
+    match (base, other) {
+        (base, other) if other.is_empty() => base,
+        (base, other) if base.is_empty() => other,
+        (Labels::List(base), Labels::List(other)) => {
+            let mut merged = list_to_optional_map(base);
+            merge_map(&mut merged, list_to_optional_map(other));
+            Labels::List(optional_map_to_list(merged))
+        }
+        (base, other) => {
+            let mut merged = labels_to_map(base);
+            merge_map(&mut merged, labels_to_map(other));
+            Labels::Map(merged)
+        }
+    }
+}
+
+fn labels_to_map(labels: Labels) -> Map<String, String> {
+    match labels {
+        Labels::List(list) => split_key_values(list),
+        Labels::Map(map) => map,
+    }
+}
+
+/// Converts `KEY=VALUE` entries into a map, a bare `KEY` has no value
+fn list_to_optional_map(entries: Vec<String>) -> Map<String, Option<SingleValue>> {
+    entries
+        .into_iter()
+        .map(|entry| match entry.split_once('=') {
+            Some((key, value)) => (
+                key.to_string(),
+                Some(SingleValue::String(value.to_string())),
+            ),
+            None => (entry, None),
+        })
+        .collect()
+}
+
+fn optional_map_to_list(map: Map<String, Option<SingleValue>>) -> Vec<String> {
+    map.into_iter()
+        .map(|(key, value)| match value {
+            Some(value) => format!("{key}={value}"),
+            None => key,
+        })
+        .collect()
+}
+
+fn merge_environment(base: Environment, other: Environment) -> Environment {
+    match (base, other) {
+        (base, other) if other.is_empty() => base,
+        (base, other) if base.is_empty() => other,
+        (Environment::List(base), Environment::List(other)) => {
+            let mut merged = list_to_optional_map(base);
+            merge_map(&mut merged, list_to_optional_map(other));
+            Environment::List(optional_map_to_list(merged))
+        }
+        (base, other) => {
+            let to_map = |env| match env {
+                Environment::List(list) => list_to_optional_map(list),
+                Environment::KvPair(map) => map,
+            };
+            let mut merged = to_map(base);
+            merge_map(&mut merged, to_map(other));
+            Environment::KvPair(merged)
+        }
+    }
+}
+
+fn merge_sysctls(base: SysCtls, other: SysCtls) -> SysCtls {
+    match (base, other) {
+        (base, other) if other.is_empty() => base,
+        (base, other) if base.is_empty() => other,
+        (SysCtls::List(base), SysCtls::List(other)) => {
+            let mut merged = list_to_optional_map(base);
+            merge_map(&mut merged, list_to_optional_map(other));
+            SysCtls::List(optional_map_to_list(merged))
+        }
+        (base, other) => {
+            let to_map = |sysctls| match sysctls {
+                SysCtls::List(list) => list_to_optional_map(list),
+                SysCtls::Map(map) => map,
+            };
+            let mut merged = to_map(base);
+            merge_map(&mut merged, to_map(other));
+            SysCtls::Map(merged)
+        }
+    }
+}
+
+fn merge_depends_on(base: DependsOnOptions, other: DependsOnOptions) -> DependsOnOptions {
+    match (base, other) {
+        (DependsOnOptions::Simple(mut base), DependsOnOptions::Simple(other)) => {
+            append_unique(&mut base, other);
+            DependsOnOptions::Simple(base)
+        }
+        (base, other) => {
+            let to_map = |depends_on| match depends_on {
+                DependsOnOptions::Simple(services) => services
+                    .into_iter()
+                    .map(|service| (service, DependsCondition::service_started()))
+                    .collect(),
+                DependsOnOptions::Conditional(map) => map,
+            };
+            let mut merged: Map<String, DependsCondition> = to_map(base);
+            merge_map(&mut merged, to_map(other));
+            DependsOnOptions::Conditional(merged)
+        }
+    }
+}
+
+fn merge_ports(base: Ports, other: Ports) -> Ports {
+    match (base, other) {
+        (base, other) if other.is_empty() => base,
+        (base, other) if base.is_empty() => other,
+        (Ports::Short(mut base), Ports::Short(other)) => {
+            append_unique(&mut base, other);
+            Ports::Short(base)
+        }
+        (Ports::Long(mut base), Ports::Long(other)) => {
+            append_unique(&mut base, other);
+            Ports::Long(base)
+        }
+        (base, other) => match (base.normalize(), other.normalize()) {
+            (Ok(mut base), Ok(other)) => {
+                append_unique(&mut base, other);
+                Ports::Long(base)
+            }
+            // Without a structured view of both sides, append the short specs
+            _ => {
+                let mut merged = ports_to_short(base);
+                append_unique(&mut merged, ports_to_short(other));
+                Ports::Short(merged)
+            }
+        },
+    }
+}
+
+fn ports_to_short(ports: Ports) -> Vec<String> {
+    match ports {
+        Ports::Short(specs) => specs,
+        Ports::Long(ports) => ports.iter().map(Port::to_short).collect(),
+    }
+}
+
+fn merge_volumes(base: Vec<Volumes>, other: Vec<Volumes>) -> Vec<Volumes> {
+    let mut merged = base;
+    for volume in other {
+        let target = volume_target(&volume);
+        match merged.iter_mut().find(|v| volume_target(v) == target) {
+            Some(existing) => *existing = volume,
+            None => merged.push(volume),
+        }
+    }
+    merged
+}
+
+/// Returns the container path of a volume mount, or the spec itself when it
+/// can't be parsed
+fn volume_target(volume: &Volumes) -> String {
+    match (volume.normalize(), volume) {
+        (Ok(volume), _) => volume.target,
+        (Err(_), Volumes::Simple(spec)) => spec.clone(),
+        (Err(_), Volumes::Advanced(volume)) => volume.target.clone(),
+    }
+}
+
+fn merge_networks(base: Networks, other: Networks) -> Networks {
+    match (base, other) {
+        (Networks::Simple(mut base), Networks::Simple(other)) => {
+            append_unique(&mut base, other);
+            Networks::Simple(base)
+        }
+        (base, other) => {
+            let mut merged = base.normalize();
+            merge_entries(&mut merged.0, other.normalize().0, merge_network_settings);
+            Networks::Advanced(merged)
+        }
+    }
+}
+
+fn merge_network_settings(
+    mut base: AdvancedNetworkSettings,
+    other: AdvancedNetworkSettings,
+) -> AdvancedNetworkSettings {
+    append_unique(&mut base.aliases, other.aliases);
+    append_unique(&mut base.link_local_ips, other.link_local_ips);
+    merge_map(&mut base.driver_opts, other.driver_opts);
+    AdvancedNetworkSettings {
+        ipv4_address: other.ipv4_address.or(base.ipv4_address),
+        ipv6_address: other.ipv6_address.or(base.ipv6_address),
+        aliases: base.aliases,
+        link_local_ips: base.link_local_ips,
+        mac_address: other.mac_address.or(base.mac_address),
+        priority: other.priority.or(base.priority),
+        gw_priority: other.gw_priority.or(base.gw_priority),
+        interface_name: other.interface_name.or(base.interface_name),
+        driver_opts: base.driver_opts,
+    }
+}
+
+/// Merges the per-device settings by device path
+fn merge_blkio_config(mut base: BlkioConfig, other: BlkioConfig) -> BlkioConfig {
+    fn merge_devices<T>(base: &mut Vec<T>, other: Vec<T>, path: fn(&T) -> &str) {
+        for device in other {
+            match base.iter_mut().find(|d| path(d) == path(&device)) {
+                Some(existing) => *existing = device,
+                None => base.push(device),
+            }
+        }
+    }
+
+    base.weight = other.weight.or(base.weight);
+    merge_devices(&mut base.weight_device, other.weight_device, |d| &d.path);
+    merge_devices(&mut base.device_read_bps, other.device_read_bps, |d| {
+        &d.path
+    });
+    merge_devices(&mut base.device_read_iops, other.device_read_iops, |d| {
+        &d.path
+    });
+    merge_devices(&mut base.device_write_bps, other.device_write_bps, |d| {
+        &d.path
+    });
+    merge_devices(&mut base.device_write_iops, other.device_write_iops, |d| {
+        &d.path
+    });
+    base
+}
+
+fn merge_secrets(base: Secrets, other: Secrets) -> Secrets {
+    match (base, other) {
+        (Secrets::Simple(mut base), Secrets::Simple(other)) => {
+            append_unique(&mut base, other);
+            Secrets::Simple(base)
+        }
+        (base, other) => {
+            let to_advanced = |secrets| match secrets {
+                Secrets::Simple(sources) => sources
+                    .into_iter()
+                    .map(|source| AdvancedSecrets {
+                        source,
+                        ..Default::default()
+                    })
+                    .collect(),
+                Secrets::Advanced(secrets) => secrets,
+            };
+            // Secrets are mounted below `/run/secrets` unless the target is
+            // an absolute path
+            let target = |secret: &AdvancedSecrets| {
+                let target = secret.target.as_deref().unwrap_or(&secret.source);
+                if target.starts_with('/') {
+                    target.to_string()
+                } else {
+                    format!("/run/secrets/{target}")
+                }
+            };
+            let mut merged: Vec<AdvancedSecrets> = to_advanced(base);
+            for secret in to_advanced(other) {
+                match merged.iter_mut().find(|s| target(s) == target(&secret)) {
+                    Some(existing) => *existing = secret,
+                    None => merged.push(secret),
+                }
+            }
+            Secrets::Advanced(merged)
+        }
+    }
+}
+
+fn merge_configs(base: Configs, other: Configs) -> Configs {
+    match (base, other) {
+        (Configs::Simple(mut base), Configs::Simple(other)) => {
+            append_unique(&mut base, other);
+            Configs::Simple(base)
+        }
+        (base, other) => {
+            let to_advanced = |configs| match configs {
+                Configs::Simple(sources) => sources
+                    .into_iter()
+                    .map(|source| AdvancedConfigs {
+                        source,
+                        ..Default::default()
+                    })
+                    .collect(),
+                Configs::Advanced(configs) => configs,
+            };
+            let target = |config: &AdvancedConfigs| {
+                let target = config.target.as_deref().unwrap_or(&config.source);
+                if target.starts_with('/') {
+                    target.to_string()
+                } else {
+                    format!("/{target}")
+                }
+            };
+            let mut merged: Vec<AdvancedConfigs> = to_advanced(base);
+            for config in to_advanced(other) {
+                match merged.iter_mut().find(|s| target(s) == target(&config)) {
+                    Some(existing) => *existing = config,
+                    None => merged.push(config),
+                }
+            }
+            Configs::Advanced(merged)
+        }
+    }
+}
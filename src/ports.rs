@@ -0,0 +1,144 @@
+use std::fmt;
+
+use crate::{Port, Ports, PublishedPort};
+
+/// A short port spec that could not be parsed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PortParseError {
+    pub spec: String,
+    pub message: String,
+}
+
+impl fmt::Display for PortParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid port {:?}: {}", self.spec, self.message)
+    }
+}
+
+impl std::error::Error for PortParseError {}
+
+impl Port {
+    /// Parses a short port spec, `[[host_ip:][published[-range]]:]target[-range][/protocol]`.
+    ///
+    /// IPv6 host addresses are written in brackets, as in `[::1]:53:53/udp`.
+    /// A target range yields one port per target, paired with the published
+    /// range when there is one, e.g. `8000-8001:80-81` yields `8000:80` and
+    /// `8001:81`. A published range for a single target is kept as a range.
+    pub fn parse_short(spec: &str) -> Result<Vec<Port>, PortParseError> {
+        let error = |message: &str| PortParseError {
+            spec: spec.to_string(),
+            message: message.to_string(),
+        };
+
+        let (rest, protocol) = match spec.split_once('/') {
+            Some((_, "")) => return Err(error("empty protocol")),
+            Some((rest, protocol @ ("tcp" | "udp" | "sctp"))) => (rest, Some(protocol.to_string())),
+            Some(_) => return Err(error("unknown protocol, expected tcp, udp or sctp")),
+            None => (spec, None),
+        };
+        let (host_ip, rest) = match rest.strip_prefix('[') {
+            Some(bracketed) => {
+                let (ip, rest) = bracketed
+                    .split_once(']')
+                    .ok_or_else(|| error("unterminated IPv6 address"))?;
+                let rest = rest
+                    .strip_prefix(':')
+                    .ok_or_else(|| error("expected ':' after the host address"))?;
+                (Some(ip), rest)
+            }
+            None => (None, rest),
+        };
+
+        let parts: Vec<&str> = rest.split(':').collect();
+        let (host_ip, published, target) = match (host_ip, parts.as_slice()) {
+            (Some(ip), [published, target]) => (Some(ip), Some(*published), *target),
+            (None, [target]) => (None, None, *target),
+            (None, [published, target]) => (None, Some(*published), *target),
+            (None, [ip, published, target]) => (Some(*ip), Some(*published), *target),
+            _ => return Err(error("too many ':' separated parts")),
+        };
+        let host_ip = host_ip.filter(|ip| !ip.is_empty()).map(str::to_string);
+        let published = published.filter(|p| !p.is_empty());
+
+        let target = parse_range(target).ok_or_else(|| error("invalid target port"))?;
+        let published = published
+            .map(|published| parse_range(published).ok_or_else(|| error("invalid published port")))
+            .transpose()?;
+
+        let port = |target, published| Port {
+            target,
+            host_ip: host_ip.clone(),
+            published,
+            protocol: protocol.clone(),
+            mode: None,
+        };
+        let (first, last) = target;
+        match published {
+            None => Ok((first..=last).map(|target| port(target, None)).collect()),
+            Some((from, to)) if first == last && from == to => {
+                Ok(vec![port(first, Some(PublishedPort::Single(from)))])
+            }
+            Some((from, to)) if first == last => Ok(vec![port(
+                first,
+                Some(PublishedPort::Range(format!("{from}-{to}"))),
+            )]),
+            Some((from, to)) if to - from == last - first => Ok((first..=last)
+                .zip(from..=to)
+                .map(|(target, published)| port(target, Some(PublishedPort::Single(published))))
+                .collect()),
+            Some(_) => Err(error(
+                "published and target port ranges have different lengths",
+            )),
+        }
+    }
+
+    /// Renders this port in the short syntax. `mode` has no short form and
+    /// is left out.
+    pub fn to_short(&self) -> String {
+        let mut spec = String::new();
+        if let Some(host_ip) = &self.host_ip {
+            if host_ip.contains(':') {
+                spec.push_str(&format!("[{host_ip}]:"));
+            } else {
+                spec.push_str(&format!("{host_ip}:"));
+            }
+        }
+        match &self.published {
+            Some(PublishedPort::Single(port)) => spec.push_str(&format!("{port}:")),
+            Some(PublishedPort::Range(range)) => spec.push_str(&format!("{range}:")),
+            None if self.host_ip.is_some() => spec.push(':'),
+            None => {}
+        }
+        spec.push_str(&self.target.to_string());
+        if let Some(protocol) = &self.protocol {
+            spec.push_str(&format!("/{protocol}"));
+        }
+        spec
+    }
+}
+
+impl Ports {
+    /// Returns the ports in their long form, parsing short specs with
+    /// [`Port::parse_short`]
+    pub fn normalize(&self) -> Result<Vec<Port>, PortParseError> {
+        match self {
+            Ports::Short(specs) => specs.iter().try_fold(Vec::new(), |mut ports, spec| {
+                ports.extend(Port::parse_short(spec)?);
+                Ok(ports)
+            }),
+            Ports::Long(ports) => Ok(ports.clone()),
+        }
+    }
+}
+
+/// Parses `port` or `first-last` into an inclusive range
+fn parse_range(range: &str) -> Option<(u16, u16)> {
+    let (first, last) = match range.split_once('-') {
+        Some((first, last)) => (first.parse().ok()?, last.parse().ok()?),
+        None => {
+            let port = range.parse().ok()?;
+            (port, port)
+        }
+    };
+    (first <= last).then_some((first, last))
+}
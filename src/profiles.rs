@@ -0,0 +1,161 @@
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::str::FromStr;
+
+use crate::interpolation::VariableSource;
+use crate::{Compose, Service, Services};
+
+/// The profiles enabled for a project, as given with `--profile` or
+/// `COMPOSE_PROFILES`. The `*` profile enables every service.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ActiveProfiles(pub Vec<String>);
+
+impl ActiveProfiles {
+    pub fn new<I, S>(profiles: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self(profiles.into_iter().map(Into::into).collect())
+    }
+
+    /// Reads the comma-separated `COMPOSE_PROFILES` variable
+    pub fn from_variables<V>(variables: &V) -> Self
+    where
+        V: VariableSource + ?Sized,
+    {
+        variables
+            .var("COMPOSE_PROFILES")
+            .map(|value| value.parse().unwrap_or_default())
+            .unwrap_or_default()
+    }
+
+    pub fn is_active(&self, profile: &str) -> bool {
+        self.0.iter().any(|p| p == "*" || p == profile)
+    }
+
+    /// Whether `service` is enabled, i.e. has no profile or an active one
+    pub fn enables(&self, service: &Service) -> bool {
+        service.profiles.is_empty() || service.profiles.iter().any(|p| self.is_active(p))
+    }
+}
+
+impl FromStr for ActiveProfiles {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::new(
+            s.split(',').map(str::trim).filter(|p| !p.is_empty()),
+        ))
+    }
+}
+
+/// The services of a project once profiles are applied
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProfileSelection {
+    /// Services without profiles, with an active profile, or explicitly
+    /// targeted
+    pub enabled: Vec<String>,
+    /// Disabled services that are required by a targeted service
+    pub pulled_in: Vec<String>,
+    /// Services that remain disabled
+    pub disabled: Vec<String>,
+    /// Dependencies of selected services on services that remain disabled
+    pub disabled_references: Vec<DisabledReference>,
+}
+
+/// A selected service depending on a disabled one
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DisabledReference {
+    pub service: String,
+    pub dependency: String,
+}
+
+impl ProfileSelection {
+    /// Whether `service` is part of the selection
+    pub fn is_selected(&self, service: &str) -> bool {
+        self.enabled
+            .iter()
+            .chain(&self.pulled_in)
+            .any(|s| s == service)
+    }
+}
+
+impl Compose {
+    /// Computes which services are part of the project for the given active
+    /// profiles. `targets` are the services named explicitly, e.g. on the
+    /// command line: they are enabled regardless of their profiles and pull in
+    /// the services they depend on, see [`Service::dependencies`].
+    pub fn select_profiles(&self, profiles: &ActiveProfiles, targets: &[&str]) -> ProfileSelection {
+        let default = Service::default();
+        let service = |name: &str| match self.services.0.get(name) {
+            Some(service) => service.as_ref().unwrap_or(&default),
+            None => &default,
+        };
+
+        let enabled: HashSet<&str> = self
+            .services
+            .0
+            .iter()
+            .filter(|(name, s)| {
+                targets.contains(&name.as_str()) || profiles.enables(s.as_ref().unwrap_or(&default))
+            })
+            .map(|(name, _)| name.as_str())
+            .collect();
+
+        let mut required = HashSet::new();
+        let mut queue: Vec<&str> = targets.to_vec();
+        while let Some(name) = queue.pop() {
+            if !required.insert(name) {
+                continue;
+            }
+            let dependencies = service(name).dependencies();
+            queue.extend(dependencies.iter().filter_map(|d| {
+                self.services
+                    .0
+                    .get_key_value(&d.service)
+                    .map(|(k, _)| k.as_str())
+            }));
+        }
+
+        let mut selection = ProfileSelection::default();
+        for name in self.services.0.keys() {
+            if enabled.contains(name.as_str()) {
+                selection.enabled.push(name.clone());
+            } else if required.contains(name.as_str()) {
+                selection.pulled_in.push(name.clone());
+            } else {
+                selection.disabled.push(name.clone());
+            }
+        }
+
+        for name in selection.enabled.iter().chain(&selection.pulled_in) {
+            for dependency in service(name).dependencies() {
+                if dependency.is_required() && selection.disabled.contains(&dependency.service) {
+                    selection.disabled_references.push(DisabledReference {
+                        service: name.clone(),
+                        dependency: dependency.service,
+                    });
+                }
+            }
+        }
+        selection
+    }
+
+    /// Returns a copy of this model only containing the services selected by
+    /// [`select_profiles`](Self::select_profiles)
+    pub fn with_profiles(&self, profiles: &ActiveProfiles, targets: &[&str]) -> Compose {
+        let selection = self.select_profiles(profiles, targets);
+        Compose {
+            services: Services(
+                self.services
+                    .0
+                    .iter()
+                    .filter(|(name, _)| selection.is_selected(name))
+                    .map(|(name, service)| (name.clone(), service.clone()))
+                    .collect(),
+            ),
+            ..self.clone()
+        }
+    }
+}
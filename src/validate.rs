@@ -0,0 +1,250 @@
+use std::fmt;
+
+use crate::graph::{link_service, mode_service, volumes_from_service};
+use crate::{
+    BuildStep, Command, Compose, Configs, DependsOnOptions, Networks, Secrets, Service,
+    ServiceHook, Volumes,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem found by [`Compose::validate`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// YAML path of the offending value, e.g. `services.web.networks[1]`
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}: {}", self.severity, self.path, self.message)
+    }
+}
+
+impl Compose {
+    /// Checks that the networks, named volumes, secrets, configs and services
+    /// referenced by services are defined, that hooks have a command and that
+    /// service dependencies don't form a cycle. An empty list means no problem
+    /// was found.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut validator = Validator {
+            compose: self,
+            diagnostics: Vec::new(),
+        };
+        for (name, service) in &self.services.0 {
+            if let Some(service) = service {
+                validator.service(&format!("services.{name}"), service);
+            }
+        }
+        if let Err(cycle) = self.services.dependency_graph().start_order() {
+            validator.error("services".to_string(), cycle.to_string());
+        }
+        validator.diagnostics
+    }
+}
+
+struct Validator<'a> {
+    compose: &'a Compose,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Validator<'_> {
+    fn error(&mut self, path: String, message: String) {
+        self.push(Severity::Error, path, message);
+    }
+
+    fn push(&mut self, severity: Severity, path: String, message: String) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            path,
+            message,
+        });
+    }
+
+    fn service(&mut self, path: &str, service: &Service) {
+        self.networks(path, &service.networks);
+        self.volumes(path, &service.volumes);
+        if let Some(secrets) = &service.secrets {
+            self.secrets(path, secrets);
+        }
+        if let Some(BuildStep::Advanced(build)) = &service.build_ {
+            if let Some(secrets) = &build.secrets {
+                self.secrets(&format!("{path}.build"), secrets);
+            }
+        }
+        if let Some(configs) = &service.configs {
+            self.configs(path, configs);
+        }
+        self.dependencies(path, service);
+        self.hooks(&format!("{path}.post_start"), &service.post_start);
+        self.hooks(&format!("{path}.pre_stop"), &service.pre_stop);
+        if let Some(develop) = &service.develop {
+            for (i, rule) in develop.watch.iter().enumerate() {
+                if let Some(exec) = &rule.exec {
+                    self.hook(format!("{path}.develop.watch[{i}].exec"), exec);
+                }
+            }
+        }
+    }
+
+    fn networks(&mut self, path: &str, networks: &Networks) {
+        let names: Vec<(String, &String)> = match networks {
+            Networks::Simple(names) => names
+                .iter()
+                .enumerate()
+                .map(|(i, name)| (format!("{path}.networks[{i}]"), name))
+                .collect(),
+            Networks::Advanced(networks) => networks
+                .0
+                .keys()
+                .map(|name| (format!("{path}.networks.{name}"), name))
+                .collect(),
+        };
+        for (path, name) in names {
+            // The default network is created implicitly
+            if name != "default" && !self.compose.networks.0.contains_key(name) {
+                self.error(path, format!("network {name:?} is not defined"));
+            }
+        }
+    }
+
+    fn volumes(&mut self, path: &str, volumes: &[Volumes]) {
+        for (i, volume) in volumes.iter().enumerate() {
+            let path = format!("{path}.volumes[{i}]");
+            let volume = match volume.normalize() {
+                Ok(volume) => volume,
+                Err(e) => {
+                    self.error(path, e.to_string());
+                    continue;
+                }
+            };
+            if volume._type != "volume" {
+                continue;
+            }
+            if let Some(name) = volume.source {
+                if !self.compose.volumes.0.contains_key(&name) {
+                    self.error(path, format!("volume {name:?} is not defined"));
+                }
+            }
+        }
+    }
+
+    fn secrets(&mut self, path: &str, secrets: &Secrets) {
+        let names: Vec<&String> = match secrets {
+            Secrets::Simple(names) => names.iter().collect(),
+            Secrets::Advanced(secrets) => secrets.iter().map(|s| &s.source).collect(),
+        };
+        for (i, name) in names.into_iter().enumerate() {
+            let defined = self
+                .compose
+                .secrets
+                .as_ref()
+                .is_some_and(|secrets| secrets.0.contains_key(name));
+            if !defined {
+                self.error(
+                    format!("{path}.secrets[{i}]"),
+                    format!("secret {name:?} is not defined"),
+                );
+            }
+        }
+    }
+
+    fn configs(&mut self, path: &str, configs: &Configs) {
+        let names: Vec<&String> = match configs {
+            Configs::Simple(names) => names.iter().collect(),
+            Configs::Advanced(configs) => configs.iter().map(|s| &s.source).collect(),
+        };
+        for (i, name) in names.into_iter().enumerate() {
+            let defined = self
+                .compose
+                .configs
+                .as_ref()
+                .is_some_and(|configs| configs.0.contains_key(name));
+            if !defined {
+                self.error(
+                    format!("{path}.configs[{i}]"),
+                    format!("config {name:?} is not defined"),
+                );
+            }
+        }
+    }
+
+    fn hooks(&mut self, path: &str, hooks: &[ServiceHook]) {
+        for (i, hook) in hooks.iter().enumerate() {
+            self.hook(format!("{path}[{i}]"), hook);
+        }
+    }
+
+    fn hook(&mut self, path: String, hook: &ServiceHook) {
+        let empty = match &hook.command {
+            Command::Simple(command) => command.trim().is_empty(),
+            Command::Args(args) => args.is_empty(),
+        };
+        if empty {
+            self.error(format!("{path}.command"), "command is empty".to_string());
+        }
+    }
+
+    fn dependencies(&mut self, path: &str, service: &Service) {
+        let services = &self.compose.services.0;
+        let mut references = Vec::new();
+        match &service.depends_on {
+            DependsOnOptions::Simple(names) => {
+                for (i, name) in names.iter().enumerate() {
+                    references.push((format!("{path}.depends_on[{i}]"), name.as_str(), true));
+                }
+            }
+            DependsOnOptions::Conditional(names) => {
+                for (name, condition) in names {
+                    let required = condition.required != Some(false);
+                    references.push((format!("{path}.depends_on.{name}"), name.as_str(), required));
+                }
+            }
+        }
+        for (i, link) in service.links.iter().enumerate() {
+            references.push((format!("{path}.links[{i}]"), link_service(link), true));
+        }
+        for (i, source) in service.volumes_from.iter().enumerate() {
+            if let Some(name) = volumes_from_service(source) {
+                references.push((format!("{path}.volumes_from[{i}]"), name, true));
+            }
+        }
+        for (field, mode) in [
+            ("network_mode", &service.network_mode),
+            ("ipc", &service.ipc),
+            ("pid", &service.pid),
+        ] {
+            if let Some(name) = mode.as_deref().and_then(mode_service) {
+                references.push((format!("{path}.{field}"), name, true));
+            }
+        }
+
+        for (path, name, required) in references {
+            if services.contains_key(name) {
+                continue;
+            }
+            // Optional dependencies are skipped when the service is missing
+            let severity = if required {
+                Severity::Error
+            } else {
+                Severity::Warning
+            };
+            self.push(severity, path, format!("service {name:?} is not defined"));
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use std::fmt;
+
+use crate::{AdvancedVolumes, Bind, Volume, Volumes};
+
+/// A short volume spec that could not be parsed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VolumeParseError {
+    pub spec: String,
+    pub message: String,
+}
+
+impl fmt::Display for VolumeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid volume {:?}: {}", self.spec, self.message)
+    }
+}
+
+impl std::error::Error for VolumeParseError {}
+
+const PROPAGATION_MODES: [&str; 6] = [
+    "shared", "slave", "private", "rshared", "rslave", "rprivate",
+];
+
+impl AdvancedVolumes {
+    /// Parses a short volume spec, `[source:]target[:mode]`.
+    ///
+    /// Sources that are paths (starting with `.`, `/` or `~`, or Windows paths
+    /// such as `C:\data`) are bind mounts, other sources are named volumes and
+    /// a spec without a source is an anonymous volume. `mode` is a comma
+    /// separated list of `ro`/`rw`, `z`/`Z` (SELinux relabeling), a bind
+    /// propagation mode such as `rshared`, and `nocopy`. Short binds create
+    /// the host path when it is missing, which is reflected in
+    /// `bind.create_host_path`.
+    pub fn parse_short(spec: &str) -> Result<AdvancedVolumes, VolumeParseError> {
+        let error = |message: &str| VolumeParseError {
+            spec: spec.to_string(),
+            message: message.to_string(),
+        };
+
+        let parts = split_spec(spec);
+        let (source, target, mode) = match parts.as_slice() {
+            [target] => (None, *target, None),
+            [source, target] => (Some(*source), *target, None),
+            [source, target, mode] => (Some(*source), *target, Some(*mode)),
+            _ => return Err(error("too many ':' separated parts")),
+        };
+        if target.is_empty() {
+            return Err(error("empty target"));
+        }
+        if !target.starts_with('/') && !is_windows_path(target) {
+            return Err(error(
+                "the target must be an absolute path in the container",
+            ));
+        }
+        let source = source.filter(|s| !s.is_empty());
+        let is_bind = source.is_some_and(is_host_path);
+
+        let mut volume = AdvancedVolumes {
+            source: source.map(str::to_string),
+            target: target.to_string(),
+            _type: if is_bind { "bind" } else { "volume" }.to_string(),
+            read_only: false,
+            bind: is_bind.then(|| Bind {
+                create_host_path: Some(true),
+                ..Default::default()
+            }),
+            volume: None,
+            tmpfs: None,
+        };
+        for option in mode.into_iter().flat_map(|mode| mode.split(',')) {
+            match (option, &mut volume.bind) {
+                ("ro", _) => volume.read_only = true,
+                ("rw", _) => volume.read_only = false,
+                ("z" | "Z", Some(bind)) => bind.selinux = Some(option.to_string()),
+                (mode, Some(bind)) if PROPAGATION_MODES.contains(&mode) => {
+                    bind.propagation = Some(mode.to_string())
+                }
+                ("nocopy", None) => {
+                    volume.volume.get_or_insert_with(Volume::default).nocopy = Some(true)
+                }
+                (option, _)
+                    if matches!(option, "z" | "Z" | "nocopy")
+                        || PROPAGATION_MODES.contains(&option) =>
+                {
+                    return Err(error(&format!(
+                        "{option:?} is not supported for this mount type"
+                    )))
+                }
+                _ => return Err(error(&format!("unknown mode {option:?}"))),
+            }
+        }
+        Ok(volume)
+    }
+
+    /// Renders this mount in the short syntax, `None` when it uses options the
+    /// short syntax can't express, such as tmpfs mounts or volume subpaths.
+    pub fn to_short(&self) -> Option<String> {
+        let mut options = Vec::new();
+        if self.read_only {
+            options.push("ro");
+        }
+        match self._type.as_str() {
+            "bind" => {
+                if self.source.is_none() || self.volume.is_some() {
+                    return None;
+                }
+                if let Some(bind) = &self.bind {
+                    if bind.create_host_path == Some(false) {
+                        return None;
+                    }
+                    options.extend(bind.selinux.as_deref());
+                    options.extend(bind.propagation.as_deref());
+                }
+            }
+            "volume" => {
+                if self.bind.is_some() {
+                    return None;
+                }
+                if let Some(volume) = &self.volume {
+                    if volume.subpath.is_some() {
+                        return None;
+                    }
+                    if volume.nocopy == Some(true) {
+                        options.push("nocopy");
+                    }
+                }
+            }
+            _ => return None,
+        }
+        if self.tmpfs.is_some() {
+            return None;
+        }
+
+        let mut spec = match &self.source {
+            Some(source) => format!("{source}:{}", self.target),
+            None => self.target.clone(),
+        };
+        if !options.is_empty() {
+            spec.push(':');
+            spec.push_str(&options.join(","));
+        }
+        Some(spec)
+    }
+}
+
+impl Volumes {
+    /// Returns the mount in its long form, parsing short specs with
+    /// [`AdvancedVolumes::parse_short`]
+    pub fn normalize(&self) -> Result<AdvancedVolumes, VolumeParseError> {
+        match self {
+            Volumes::Simple(spec) => AdvancedVolumes::parse_short(spec),
+            Volumes::Advanced(volume) => Ok(volume.clone()),
+        }
+    }
+}
+
+/// Splits a short spec on `:`, keeping Windows drive letters such as `C:\data`
+/// with the path they belong to
+fn split_spec(spec: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let bytes = spec.as_bytes();
+    for (i, c) in spec.char_indices() {
+        if c != ':' {
+            continue;
+        }
+        let is_drive = i == start + 1
+            && bytes[start].is_ascii_alphabetic()
+            && matches!(bytes.get(i + 1), Some(b'\\' | b'/'));
+        if !is_drive {
+            parts.push(&spec[start..i]);
+            start = i + 1;
+        }
+    }
+    parts.push(&spec[start..]);
+    parts
+}
+
+fn is_host_path(source: &str) -> bool {
+    source.starts_with(['.', '/', '~']) || is_windows_path(source)
+}
+
+/// Whether `path` is a Windows path, e.g. `C:\data`, `C:/data` or
+/// `\\server\share`
+pub(crate) fn is_windows_path(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    path.starts_with('\\')
+        || (bytes.len() >= 3
+            && bytes[0].is_ascii_alphabetic()
+            && bytes[1] == b':'
+            && matches!(bytes[2], b'\\' | b'/'))
+}
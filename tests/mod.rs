@@ -1,9 +1,9 @@
 #[cfg(all(feature = "norway", not(feature = "yaml"), not(feature = "yml")))]
-use serde_norway::from_str;
+use serde_norway::{from_str, to_string};
 #[cfg(feature = "yaml")]
-use serde_yaml::from_str;
+use serde_yaml::{from_str, to_string};
 #[cfg(all(feature = "yml", not(feature = "yaml"), not(feature = "norway")))]
-use serde_yml::from_str;
+use serde_yml::{from_str, to_string};
 
 #[test]
 fn parse_compose() {
@@ -19,8 +19,8 @@ fn parse_compose() {
         // Can't figure out why this specific file fails on the top-level enum, it passed on the test below
         let entry_path = entry.display().to_string();
 
-        let skip_list = vec![
-            format!("v3-full"),
+        let skip_list = [
+            "v3-full".to_string(),
             format!("extends{MAIN_SEPARATOR}verbose-and-shorthand.yml"),
             format!("net-container{MAIN_SEPARATOR}v2-invalid.yml"),
             format!("v2-simple{MAIN_SEPARATOR}links-invalid.yml"),
@@ -182,3 +182,2010 @@ fn include_path_supports_multiple_entries() {
         ]
     );
 }
+
+#[test]
+fn load_project_from_files() {
+    use docker_compose_types::{Command, Compose};
+
+    let project = Compose::load(&[
+        "tests/fixtures/override-yaml-files/docker-compose.yml",
+        "tests/fixtures/override-yaml-files/docker-compose.override.yaml",
+    ])
+    .unwrap();
+
+    assert!(project.project_directory.is_absolute());
+    assert!(project
+        .project_directory
+        .ends_with("tests/fixtures/override-yaml-files"));
+    assert_eq!(project.files.len(), 2);
+
+    let services = &project.compose.services.0;
+    assert!(services.contains_key("web"));
+    let db = services["db"].as_ref().unwrap();
+    assert_eq!(db.command, Some(Command::Simple("top".to_string())));
+}
+
+#[test]
+fn load_project_with_explicit_directory() {
+    use docker_compose_types::{ComposeLoader, LoadError};
+
+    let project = ComposeLoader::new()
+        .file("tests/fixtures/env-file/docker-compose.yml")
+        .project_directory("tests/fixtures")
+        .load()
+        .unwrap();
+    assert!(project.project_directory.ends_with("tests/fixtures"));
+    assert!(project
+        .resolve_path("./env-file/test.env")
+        .ends_with("tests/fixtures/env-file/test.env"));
+
+    assert!(matches!(
+        ComposeLoader::new().load(),
+        Err(LoadError::NoFiles)
+    ));
+    assert!(matches!(
+        ComposeLoader::new()
+            .file("tests/fixtures/does-not-exist.yml")
+            .load(),
+        Err(LoadError::Io { .. })
+    ));
+}
+
+#[test]
+fn resolve_includes() {
+    use docker_compose_types::{Compose, Includes};
+
+    let project = Compose::load(&["tests/fixtures/includes/long.yml"]).unwrap();
+    let services = &project.compose.services.0;
+    assert!(project.compose.includes.is_none());
+    assert!(services.contains_key("foo"));
+    assert!(services.contains_key("web"));
+    assert!(services.contains_key("busy"));
+
+    let project = Compose::load(&["tests/fixtures/includes/short.yml"]).unwrap();
+    assert!(project.compose.services.0.contains_key("web"));
+    assert!(!matches!(
+        project.compose.includes,
+        Some(Includes::Short(_))
+    ));
+}
+
+#[test]
+fn resolve_includes_rebases_relative_paths() {
+    use docker_compose_types::{BuildStep, Compose, StringOrList, Volumes};
+
+    let project = Compose::load(&["tests/fixtures/includes/nested.yml"]).unwrap();
+    let app = project.compose.services.0["app"].as_ref().unwrap();
+    assert_eq!(app.build_, Some(BuildStep::Simple("./sub/app".to_string())));
+    assert_eq!(
+        app.env_file,
+        Some(StringOrList::Simple("./sub/app.env".to_string()))
+    );
+    assert_eq!(
+        app.volumes,
+        vec![
+            Volumes::Simple("./sub/data:/data".to_string()),
+            Volumes::Simple("cache:/cache".to_string()),
+        ]
+    );
+    assert!(project.compose.volumes.0.contains_key("cache"));
+}
+
+#[test]
+fn resolve_includes_errors() {
+    use docker_compose_types::{Compose, LoadError};
+
+    match Compose::load(&["tests/fixtures/includes/cycle-a.yml"]) {
+        Err(LoadError::IncludeCycle(files)) => {
+            assert_eq!(files.len(), 3);
+            assert_eq!(files.first(), files.last());
+        }
+        other => panic!("expected an include cycle, got {other:?}"),
+    }
+
+    match Compose::load(&["tests/fixtures/includes/conflict.yml"]) {
+        Err(LoadError::IncludeConflict { kind, name, .. }) => {
+            assert_eq!(kind, "services");
+            assert_eq!(name, "web");
+        }
+        other => panic!("expected an include conflict, got {other:?}"),
+    }
+}
+
+#[test]
+fn resolve_extends() {
+    use docker_compose_types::{Command, Compose, Environment, SingleValue};
+
+    let project = Compose::load(&["tests/fixtures/extends/docker-compose.yml"]).unwrap();
+    let myweb = project.compose.services.0["myweb"].as_ref().unwrap();
+    assert!(myweb.extends.is_empty());
+    assert_eq!(myweb.image.as_deref(), Some("busybox"));
+    assert_eq!(myweb.net.as_deref(), Some("host"));
+    assert_eq!(myweb.command, Some(Command::Simple("top".to_string())));
+    assert_eq!(myweb.links, vec!["mydb:db".to_string()]);
+    let env = match &myweb.environment {
+        Environment::KvPair(env) => env,
+        other => panic!("expected merged environment map, got {other:?}"),
+    };
+    let value = |key: &str| env[key].as_ref().map(ToString::to_string);
+    assert_eq!(value("FOO").as_deref(), Some("1"));
+    assert_eq!(value("BAR").as_deref(), Some("2"));
+    assert_eq!(env["BAZ"], Some(SingleValue::String("2".to_string())));
+
+    let project = Compose::load(&["tests/fixtures/extends/nested.yml"]).unwrap();
+    let myweb = project.compose.services.0["myweb"].as_ref().unwrap();
+    assert_eq!(
+        myweb.environment,
+        Environment::List(vec!["FOO=2".into(), "BAR=2".into()])
+    );
+
+    let project = Compose::load(&["tests/fixtures/extends/specify-file-as-self.yml"]).unwrap();
+    let myweb = project.compose.services.0["myweb"].as_ref().unwrap();
+    assert_eq!(
+        myweb.environment,
+        Environment::List(vec!["YEP=1".into(), "BAZ=3".into(), "BAR=1".into()])
+    );
+}
+
+#[test]
+fn resolve_extends_errors() {
+    use docker_compose_types::{Compose, LoadError};
+
+    match Compose::load(&["tests/fixtures/extends/circle-1.yml"]) {
+        Err(LoadError::ExtendsCycle(services)) => {
+            let names: Vec<&str> = services.iter().map(|(_, s)| s.as_str()).collect();
+            assert_eq!(names, ["web", "other", "web"]);
+        }
+        other => panic!("expected an extends cycle, got {other:?}"),
+    }
+
+    match Compose::load(&["tests/fixtures/extends/nonexistent-service.yml"]) {
+        Err(LoadError::UnknownService { service, .. }) => assert_eq!(service, "foo"),
+        other => panic!("expected an unknown service, got {other:?}"),
+    }
+}
+
+#[test]
+fn merge_services() {
+    use docker_compose_types::{
+        Command, DependsOnOptions, Environment, Labels, Ports, PublishedPort, Secrets, Service,
+        Volumes,
+    };
+
+    let base: Service = from_str(
+        r#"
+image: app:1
+command: ["run", "--base"]
+environment:
+  - FOO=1
+  - BAR=1
+labels:
+  - com.example.tier=web
+ports:
+  - "8080:80"
+expose: ["9000"]
+dns: [1.1.1.1]
+depends_on: [db]
+volumes:
+  - ./data:/data
+  - logs:/var/log
+"#,
+    )
+    .unwrap();
+    let over: Service = from_str(
+        r#"
+image: app:2
+command: run --override
+environment:
+  BAR: "2"
+  BAZ: "3"
+labels:
+  com.example.owner: ops
+ports:
+  - target: 443
+    published: 8443
+expose: ["9000", "9001"]
+dns: [1.1.1.1, 8.8.8.8]
+depends_on:
+  cache:
+    condition: service_healthy
+volumes:
+  - ./other:/data
+"#,
+    )
+    .unwrap();
+
+    let merged = base.merge(over);
+    assert_eq!(merged.image.as_deref(), Some("app:2"));
+    assert_eq!(
+        merged.command,
+        Some(Command::Simple("run --override".to_string()))
+    );
+    match &merged.environment {
+        Environment::KvPair(env) => {
+            let keys: Vec<&str> = env.keys().map(String::as_str).collect();
+            assert_eq!(keys, ["FOO", "BAR", "BAZ"]);
+            assert_eq!(env["BAR"].as_ref().unwrap().to_string(), "2");
+        }
+        other => panic!("expected environment map, got {other:?}"),
+    }
+    match &merged.labels {
+        Labels::Map(labels) => {
+            assert_eq!(labels["com.example.tier"], "web");
+            assert_eq!(labels["com.example.owner"], "ops");
+        }
+        other => panic!("expected label map, got {other:?}"),
+    }
+    match &merged.ports {
+        Ports::Long(ports) => {
+            assert_eq!(ports.len(), 2);
+            assert_eq!(ports[0].target, 80);
+            assert_eq!(ports[0].published, Some(PublishedPort::Single(8080)));
+            assert_eq!(ports[1].target, 443);
+        }
+        other => panic!("expected long ports, got {other:?}"),
+    }
+    assert_eq!(merged.expose, ["9000", "9001"]);
+    assert_eq!(merged.dns, ["1.1.1.1", "8.8.8.8"]);
+    match &merged.depends_on {
+        DependsOnOptions::Conditional(deps) => {
+            assert_eq!(deps["db"].condition, "service_started");
+            assert_eq!(deps["cache"].condition, "service_healthy");
+        }
+        other => panic!("expected conditional depends_on, got {other:?}"),
+    }
+    assert_eq!(
+        merged.volumes,
+        vec![
+            Volumes::Simple("./other:/data".to_string()),
+            Volumes::Simple("logs:/var/log".to_string()),
+        ]
+    );
+
+    // Ports that can't be parsed keep both sides in the short syntax
+    let merged = Service {
+        ports: Ports::Short(vec!["8080:80".to_string(), "${HTTP_PORT}".to_string()]),
+        ..Default::default()
+    }
+    .merge(from_str("ports:\n  - target: 443\n    published: 8443\n").unwrap());
+    assert_eq!(
+        merged.ports,
+        Ports::Short(vec![
+            "8080:80".to_string(),
+            "${HTTP_PORT}".to_string(),
+            "8443:443".to_string()
+        ])
+    );
+
+    // Secrets are merged by their mount point, `/run/secrets/<source>` by
+    // default
+    let merged = from_str::<Service>("secrets: [token, cert]")
+        .unwrap()
+        .merge(
+            from_str(
+                r#"
+secrets:
+  - source: token
+    target: /run/secrets/token
+    mode: "0400"
+  - source: cert
+    target: cert.pem
+"#,
+            )
+            .unwrap(),
+        );
+    let Some(Secrets::Advanced(secrets)) = merged.secrets else {
+        panic!("expected long syntax secrets");
+    };
+    let targets: Vec<_> = secrets
+        .iter()
+        .map(|s| (s.source.as_str(), s.target.as_deref()))
+        .collect();
+    assert_eq!(
+        targets,
+        [
+            ("token", Some("/run/secrets/token")),
+            ("cert", None),
+            ("cert", Some("cert.pem"))
+        ]
+    );
+    assert_eq!(secrets[0].mode.as_deref(), Some("0400"));
+}
+
+#[test]
+fn merge_nested_mappings() {
+    use docker_compose_types::{ByteSize, Service};
+
+    let base: Service = from_str(
+        r#"
+deploy:
+  resources:
+    limits:
+      memory: 1g
+      cpus: "0.5"
+      pids: 100
+    reservations:
+      generic_resources:
+        - discrete_resource_spec: {kind: gpu, value: 1}
+  update_config:
+    parallelism: 2
+  rollback_config:
+    parallelism: 1
+    delay: 5s
+  restart_policy:
+    condition: on-failure
+    max_attempts: 3
+  placement:
+    constraints: [node.role == worker]
+logging:
+  driver: json-file
+  options:
+    max-size: 10m
+"#,
+    )
+    .unwrap();
+    let over: Service = from_str(
+        r#"
+deploy:
+  resources:
+    limits:
+      cpus: "1"
+    reservations:
+      memory: 1g
+      generic_resources:
+        - discrete_resource_spec: {kind: ssd, value: 2}
+  update_config:
+    delay: 10s
+  rollback_config:
+    order: start-first
+  restart_policy:
+    max_attempts: 5
+  placement:
+    constraints: [node.labels.zone == a]
+logging:
+  options:
+    max-file: "3"
+"#,
+    )
+    .unwrap();
+
+    let merged = base.merge(over);
+    let logging = merged.logging.unwrap();
+    let deploy = merged.deploy.unwrap();
+    let resources = deploy.resources.unwrap();
+    let limits = resources.limits.unwrap();
+    assert_eq!(limits.memory, Some(ByteSize::new(1 << 30)));
+    assert_eq!(limits.cpus.as_deref(), Some("1"));
+    assert_eq!(limits.pids, Some(100));
+    let reservations = resources.reservations.unwrap();
+    assert_eq!(reservations.memory, Some(ByteSize::new(1 << 30)));
+    let kinds: Vec<_> = reservations
+        .generic_resources
+        .iter()
+        .filter_map(|r| r.discrete_resource_spec.as_ref())
+        .map(|spec| spec.kind.as_str())
+        .collect();
+    assert_eq!(kinds, ["gpu", "ssd"]);
+    let update_config = deploy.update_config.unwrap();
+    assert_eq!(update_config.parallelism, Some(2));
+    assert_eq!(
+        update_config.delay.map(|d| d.to_string()).as_deref(),
+        Some("10s")
+    );
+    let rollback_config = deploy.rollback_config.unwrap();
+    assert_eq!(rollback_config.parallelism, Some(1));
+    assert_eq!(rollback_config.order.as_deref(), Some("start-first"));
+    let restart_policy = deploy.restart_policy.unwrap();
+    assert_eq!(restart_policy.condition.as_deref(), Some("on-failure"));
+    assert_eq!(restart_policy.max_attempts, Some(5));
+    assert_eq!(
+        deploy.placement.unwrap().constraints,
+        ["node.role == worker", "node.labels.zone == a"]
+    );
+    assert_eq!(logging.driver.as_deref(), Some("json-file"));
+    let options = logging.options.unwrap();
+    assert_eq!(options.len(), 2);
+}
+
+#[test]
+fn merge_compose_files() {
+    use docker_compose_types::{Compose, MapOrEmpty};
+
+    let base: Compose = from_str(
+        r#"
+name: base
+services:
+  web:
+    image: nginx
+    environment:
+      A: "1"
+volumes:
+  data:
+    driver: local
+networks:
+  front: {}
+"#,
+    )
+    .unwrap();
+    let over: Compose = from_str(
+        r#"
+services:
+  web:
+    environment:
+      B: "2"
+  worker:
+    image: worker
+volumes:
+  data:
+    labels:
+      backup: "true"
+networks:
+  front:
+    internal: true
+"#,
+    )
+    .unwrap();
+
+    let merged = Compose::merge(base, over);
+    assert_eq!(merged.name.as_deref(), Some("base"));
+    let web = merged.services.0["web"].as_ref().unwrap();
+    assert_eq!(web.image.as_deref(), Some("nginx"));
+    assert!(!web.environment.is_empty());
+    assert!(merged.services.0.contains_key("worker"));
+    match &merged.volumes.0["data"] {
+        MapOrEmpty::Map(volume) => {
+            assert_eq!(volume.driver.as_deref(), Some("local"));
+            assert!(!volume.labels.is_empty());
+        }
+        MapOrEmpty::Empty => panic!("expected merged volume"),
+    }
+    match &merged.networks.0["front"] {
+        MapOrEmpty::Map(network) => assert!(network.internal),
+        MapOrEmpty::Empty => panic!("expected merged network"),
+    }
+}
+
+#[test]
+fn interpolate_templates() {
+    use docker_compose_types::{interpolate, InterpolationErrorKind};
+    use std::collections::HashMap;
+
+    let vars: HashMap<String, String> = [("SET", "value"), ("EMPTY", "")]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    let cases = [
+        ("$SET", "value"),
+        ("${SET}-suffix", "value-suffix"),
+        ("${UNSET}", ""),
+        ("${UNSET:-default}", "default"),
+        ("${EMPTY:-default}", "default"),
+        ("${EMPTY-default}", ""),
+        ("${UNSET-default}", "default"),
+        ("${SET:+alt}", "alt"),
+        ("${EMPTY:+alt}", ""),
+        ("${EMPTY+alt}", "alt"),
+        ("${UNSET+alt}", ""),
+        ("${UNSET:-${ALSO_UNSET:-${SET}}}", "value"),
+        ("$${SET} costs $$5", "${SET} costs $5"),
+    ];
+    for (template, expected) in cases {
+        assert_eq!(
+            interpolate(template, &vars).unwrap(),
+            expected,
+            "{template}"
+        );
+    }
+
+    let err = interpolate("${EMPTY:?must be set}", &vars).unwrap_err();
+    assert_eq!(
+        err.kind,
+        InterpolationErrorKind::MissingVariable {
+            name: "EMPTY".to_string(),
+            message: "must be set".to_string()
+        }
+    );
+    assert!(interpolate("${EMPTY?must be set}", &vars).is_ok());
+    assert!(matches!(
+        interpolate("${SET", &vars).unwrap_err().kind,
+        InterpolationErrorKind::InvalidSyntax(_)
+    ));
+}
+
+#[test]
+fn load_project_with_interpolation() {
+    use docker_compose_types::{Command, ComposeLoader, LoadError, Ports};
+    use std::collections::HashMap;
+
+    let vars: HashMap<String, String> = [("IMAGE", "busybox"), ("HOST_PORT", "8080")]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    let project = ComposeLoader::new()
+        .file("tests/fixtures/environment-interpolation/docker-compose.yml")
+        .variables(vars.clone())
+        .load()
+        .unwrap();
+    let web = project.compose.services.0["web"].as_ref().unwrap();
+    assert_eq!(web.image.as_deref(), Some("busybox"));
+    assert_eq!(web.ports, Ports::Short(vec!["8080:8000".to_string()]));
+    assert_eq!(web.hostname.as_deref(), Some("host-"));
+    assert_eq!(web.command, Some(Command::Simple("${ESCAPED}".to_string())));
+
+    let project = ComposeLoader::new()
+        .file("tests/fixtures/environment-interpolation-with-defaults/docker-compose.yml")
+        .variables(vars)
+        .load()
+        .unwrap();
+    let web = project.compose.services.0["web"].as_ref().unwrap();
+    assert_eq!(web.image.as_deref(), Some("busybox"));
+    assert_eq!(web.ports, Ports::Short(vec!["8080:8000".to_string()]));
+
+    let err = ComposeLoader::new()
+        .file("tests/fixtures/required-variable/docker-compose.yml")
+        .variables(HashMap::new())
+        .load()
+        .unwrap_err();
+    match &err {
+        LoadError::Interpolation { path, source } => {
+            assert!(path.ends_with("required-variable/docker-compose.yml"));
+            assert_eq!(source.path, "services.web.environment.TOKEN");
+        }
+        other => panic!("expected an interpolation error, got {other:?}"),
+    }
+    assert!(err.to_string().contains("TOKEN"));
+}
+
+#[test]
+fn parse_dotenv() {
+    use docker_compose_types::Dotenv;
+    use std::collections::HashMap;
+
+    let input = r#"
+# a comment
+export EXPORTED=yes
+UNQUOTED = plain value # trailing comment
+HASH=no#comment
+SINGLE='literal ${EXPORTED} \n'
+DOUBLE="tab\tand \"quotes\" and \$DOLLAR"
+MULTI="first
+second"
+REFERENCE=${EXPORTED}-${HOST:-none}
+FROM_HOST
+EMPTY=
+not valid=1
+UNTERMINATED="oops
+"#;
+    let host: HashMap<String, String> = [("FROM_HOST".to_string(), "host".to_string())].into();
+    let dotenv = Dotenv::parse_with(input, &host);
+
+    let expected: Vec<(String, Option<String>)> = [
+        ("EXPORTED", Some("yes")),
+        ("UNQUOTED", Some("plain value")),
+        ("HASH", Some("no#comment")),
+        ("SINGLE", Some("literal ${EXPORTED} \\n")),
+        ("DOUBLE", Some("tab\tand \"quotes\" and $DOLLAR")),
+        ("MULTI", Some("first\nsecond")),
+        ("REFERENCE", Some("yes-none")),
+        ("FROM_HOST", None),
+        ("EMPTY", Some("")),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v.map(str::to_string)))
+    .collect();
+    assert_eq!(dotenv.vars, expected);
+
+    let lines: Vec<usize> = dotenv.errors.iter().map(|e| e.line).collect();
+    assert_eq!(lines, [13, 14]);
+
+    let dotenv = Dotenv::parse("BARE\nBARE_TOO=1");
+    assert_eq!(dotenv.vars[0], ("BARE".to_string(), None));
+    assert_eq!(dotenv.get("BARE"), None);
+    assert_eq!(dotenv.get("BARE_TOO"), Some("1"));
+}
+
+#[test]
+fn load_project_with_env_files() {
+    use docker_compose_types::{ComposeLoader, LoadError, Ports};
+    use std::collections::HashMap;
+
+    let project = ComposeLoader::new()
+        .file("tests/fixtures/default-env-file/docker-compose.yml")
+        .variables(HashMap::new())
+        .load()
+        .unwrap();
+    assert_eq!(project.dotenv.get("IMAGE"), Some("alpine:latest"));
+    let web = project.compose.services.0["web"].as_ref().unwrap();
+    assert_eq!(web.image.as_deref(), Some("alpine:latest"));
+    assert_eq!(
+        web.ports,
+        Ports::Short(vec!["5643".to_string(), "9999".to_string()])
+    );
+
+    let host: HashMap<String, String> = [("PORT1".to_string(), "1234".to_string())].into();
+    let project = ComposeLoader::new()
+        .file("tests/fixtures/default-env-file/docker-compose.yml")
+        .env_file("tests/fixtures/default-env-file/alt/.env")
+        .variables(host)
+        .load()
+        .unwrap();
+    let web = project.compose.services.0["web"].as_ref().unwrap();
+    assert_eq!(web.image.as_deref(), Some("alpine:3.10.1"));
+    assert_eq!(
+        web.ports,
+        Ports::Short(vec!["1234".to_string(), "4449".to_string()])
+    );
+
+    let project = ComposeLoader::new()
+        .file("tests/fixtures/includes/env-file.yml")
+        .variables(HashMap::new())
+        .load()
+        .unwrap();
+    let tagged = project.compose.services.0["tagged"].as_ref().unwrap();
+    assert_eq!(tagged.image.as_deref(), Some("busybox:1.36"));
+
+    // Include env files are only read for interpolation
+    let loader = ComposeLoader::new()
+        .file("tests/fixtures/includes/broken-env-file.yml")
+        .variables(HashMap::new());
+    assert!(matches!(loader.load(), Err(LoadError::EnvFile { .. })));
+    let project = loader.interpolate(false).load().unwrap();
+    let tagged = project.compose.services.0["tagged"].as_ref().unwrap();
+    assert_eq!(tagged.image.as_deref(), Some("busybox:${TAG}"));
+}
+
+#[test]
+fn select_profiles() {
+    use docker_compose_types::{ActiveProfiles, Compose, DisabledReference};
+    use std::collections::HashMap;
+
+    let compose = Compose::load(&["tests/fixtures/profiles/docker-compose.yml"])
+        .unwrap()
+        .compose;
+
+    let selection = compose.select_profiles(&ActiveProfiles::default(), &[]);
+    assert_eq!(selection.enabled, ["foo"]);
+    assert_eq!(selection.disabled, ["bar", "baz", "zot"]);
+    assert!(selection.disabled_references.is_empty());
+
+    let selection = compose.select_profiles(&"test".parse().unwrap(), &[]);
+    assert_eq!(selection.enabled, ["foo", "bar", "baz"]);
+    assert_eq!(selection.disabled, ["zot"]);
+
+    let all = compose.select_profiles(&ActiveProfiles::new(["*"]), &[]);
+    assert_eq!(all.enabled, ["foo", "bar", "baz", "zot"]);
+
+    let selection = compose.select_profiles(&ActiveProfiles::new(["debug"]), &[]);
+    assert_eq!(selection.enabled, ["foo", "zot"]);
+    assert_eq!(
+        selection.disabled_references,
+        [DisabledReference {
+            service: "zot".to_string(),
+            dependency: "bar".to_string(),
+        }]
+    );
+
+    let selection = compose.select_profiles(&ActiveProfiles::default(), &["zot"]);
+    assert_eq!(selection.enabled, ["foo", "zot"]);
+    assert_eq!(selection.pulled_in, ["bar"]);
+    assert_eq!(selection.disabled, ["baz"]);
+    assert!(selection.disabled_references.is_empty());
+
+    let filtered = compose.with_profiles(&ActiveProfiles::default(), &["baz"]);
+    let names: Vec<&String> = filtered.services.0.keys().collect();
+    assert_eq!(names.len(), 3);
+    assert!(!filtered.services.0.contains_key("zot"));
+
+    let env: HashMap<String, String> =
+        [("COMPOSE_PROFILES".to_string(), " test, debug ,".to_string())].into();
+    assert_eq!(
+        ActiveProfiles::from_variables(&env),
+        ActiveProfiles::new(["test", "debug"])
+    );
+}
+
+#[test]
+fn dependency_graph() {
+    use docker_compose_types::{Compose, DependencyCycle, DependencyKind};
+
+    let compose = Compose::load(&["tests/fixtures/dependencies/docker-compose.yml"])
+        .unwrap()
+        .compose;
+    let graph = compose.services.dependency_graph();
+
+    let web: Vec<_> = graph
+        .dependencies("web")
+        .iter()
+        .map(|d| (d.service.as_str(), d.kind, d.condition.condition.as_str()))
+        .collect();
+    assert_eq!(
+        web,
+        [
+            ("api", DependencyKind::DependsOn, "service_healthy"),
+            ("cache", DependencyKind::DependsOn, "service_started"),
+        ]
+    );
+    assert_eq!(graph.dependencies("web")[0].condition.restart, Some(true));
+    assert!(!graph.dependencies("web")[1].is_required());
+    let api: Vec<_> = graph
+        .dependencies("api")
+        .iter()
+        .map(|d| (d.service.as_str(), d.kind))
+        .collect();
+    assert_eq!(
+        api,
+        [
+            ("db", DependencyKind::DependsOn),
+            ("data", DependencyKind::VolumesFrom)
+        ]
+    );
+    let sidecar: Vec<_> = graph
+        .dependencies("sidecar")
+        .iter()
+        .map(|d| (d.service.as_str(), d.kind))
+        .collect();
+    assert_eq!(
+        sidecar,
+        [
+            ("api", DependencyKind::NetworkMode),
+            ("web", DependencyKind::Pid)
+        ]
+    );
+    assert_eq!(graph.dependents("api"), ["web", "sidecar"]);
+
+    assert_eq!(
+        graph.start_waves().unwrap(),
+        [
+            vec!["data", "cache"],
+            vec!["db"],
+            vec!["api"],
+            vec!["web"],
+            vec!["sidecar"],
+        ]
+    );
+    assert_eq!(
+        graph.start_order().unwrap(),
+        ["data", "cache", "db", "api", "web", "sidecar"]
+    );
+    assert_eq!(
+        graph.stop_order().unwrap(),
+        ["sidecar", "web", "api", "db", "cache", "data"]
+    );
+
+    let compose = Compose::load(&["tests/fixtures/dependencies-cycle/docker-compose.yml"])
+        .unwrap()
+        .compose;
+    let error = compose
+        .services
+        .dependency_graph()
+        .start_order()
+        .unwrap_err();
+    assert_eq!(
+        error,
+        DependencyCycle(vec!["a".into(), "b".into(), "c".into(), "a".into()])
+    );
+    assert_eq!(
+        error.to_string(),
+        "dependency cycle detected: a -> b -> c -> a"
+    );
+}
+
+#[test]
+fn validate_references() {
+    use docker_compose_types::{Compose, Severity};
+
+    let compose = Compose::load(&["tests/fixtures/validation/docker-compose.yml"])
+        .unwrap()
+        .compose;
+    let diagnostics: Vec<_> = compose
+        .validate()
+        .into_iter()
+        .map(|d| (d.severity, d.path, d.message))
+        .collect();
+    let error =
+        |path: &str, message: &str| (Severity::Error, path.to_string(), message.to_string());
+    assert_eq!(
+        diagnostics,
+        [
+            error(
+                "services.web.networks[1]",
+                "network \"back\" is not defined"
+            ),
+            error("services.web.volumes[1]", "volume \"cache\" is not defined"),
+            error("services.web.volumes[4]", "volume \"logs\" is not defined"),
+            error(
+                "services.web.secrets[1]",
+                "secret \"missing\" is not defined"
+            ),
+            (
+                Severity::Warning,
+                "services.web.depends_on.metrics".to_string(),
+                "service \"metrics\" is not defined".to_string()
+            ),
+            error(
+                "services.worker.networks.back",
+                "network \"back\" is not defined"
+            ),
+            error(
+                "services.worker.links[0]",
+                "service \"queue\" is not defined"
+            ),
+        ]
+    );
+    assert_eq!(
+        compose.validate()[0].to_string(),
+        "error: services.web.networks[1]: network \"back\" is not defined"
+    );
+
+    let compose = Compose::load(&["tests/fixtures/dependencies/docker-compose.yml"])
+        .unwrap()
+        .compose;
+    assert!(compose.validate().is_empty());
+    let compose = Compose::load(&["tests/fixtures/dependencies-cycle/docker-compose.yml"])
+        .unwrap()
+        .compose;
+    assert_eq!(
+        compose.validate()[0].message,
+        "dependency cycle detected: a -> b -> c -> a"
+    );
+}
+
+#[test]
+fn parse_short_ports() {
+    use docker_compose_types::{Port, Ports, PublishedPort};
+
+    let port = |target, host_ip: Option<&str>, published, protocol: Option<&str>| Port {
+        target,
+        host_ip: host_ip.map(str::to_string),
+        published,
+        protocol: protocol.map(str::to_string),
+        mode: None,
+    };
+
+    assert_eq!(
+        Port::parse_short("80").unwrap(),
+        [port(80, None, None, None)]
+    );
+    assert_eq!(
+        Port::parse_short("8080:80").unwrap(),
+        [port(80, None, Some(PublishedPort::Single(8080)), None)]
+    );
+    assert_eq!(
+        Port::parse_short("127.0.0.1::5000/tcp").unwrap(),
+        [port(5000, Some("127.0.0.1"), None, Some("tcp"))]
+    );
+    assert_eq!(
+        Port::parse_short("[::1]:53:53/udp").unwrap(),
+        [port(
+            53,
+            Some("::1"),
+            Some(PublishedPort::Single(53)),
+            Some("udp")
+        )]
+    );
+    assert_eq!(
+        Port::parse_short("9090-9091:8080").unwrap(),
+        [port(
+            8080,
+            None,
+            Some(PublishedPort::Range("9090-9091".to_string())),
+            None
+        )]
+    );
+    assert_eq!(
+        Port::parse_short("127.0.0.1:8000-8001:80-81/udp").unwrap(),
+        [
+            port(
+                80,
+                Some("127.0.0.1"),
+                Some(PublishedPort::Single(8000)),
+                Some("udp")
+            ),
+            port(
+                81,
+                Some("127.0.0.1"),
+                Some(PublishedPort::Single(8001)),
+                Some("udp")
+            ),
+        ]
+    );
+    assert_eq!(Port::parse_short("3000-3002").unwrap().len(), 3);
+    assert_eq!(
+        Port::parse_short("5000/sctp").unwrap()[0]
+            .protocol
+            .as_deref(),
+        Some("sctp")
+    );
+
+    for invalid in [
+        "",
+        "http",
+        "80/",
+        "70000",
+        "90-80",
+        "8000-8002:80-81",
+        "1:2:3:4",
+        "[::1:53:53",
+        "80/tcp/udp",
+        "80/http",
+    ] {
+        assert!(Port::parse_short(invalid).is_err(), "{invalid}");
+    }
+    assert_eq!(
+        Port::parse_short("8000-8002:80-81")
+            .unwrap_err()
+            .to_string(),
+        "invalid port \"8000-8002:80-81\": published and target port ranges have different lengths"
+    );
+
+    for spec in [
+        "80",
+        "8080:80",
+        "127.0.0.1::5000/tcp",
+        "[::1]:53:53/udp",
+        "9090-9091:8080",
+    ] {
+        assert_eq!(Port::parse_short(spec).unwrap()[0].to_short(), spec);
+    }
+
+    let ports = Ports::Short(vec!["80".to_string(), "8000-8001:90-91".to_string()]);
+    let normalized = ports.normalize().unwrap();
+    assert_eq!(normalized.len(), 3);
+    assert_eq!(
+        Ports::Long(normalized.clone()).normalize().unwrap(),
+        normalized
+    );
+}
+
+#[test]
+fn parse_short_volumes() {
+    use docker_compose_types::{AdvancedVolumes, Bind, Volume, Volumes};
+
+    let bind = AdvancedVolumes::parse_short("./data:/data:ro,z").unwrap();
+    assert_eq!(bind._type, "bind");
+    assert_eq!(bind.source.as_deref(), Some("./data"));
+    assert_eq!(bind.target, "/data");
+    assert!(bind.read_only);
+    assert_eq!(
+        bind.bind,
+        Some(Bind {
+            propagation: None,
+            create_host_path: Some(true),
+            selinux: Some("z".to_string()),
+        })
+    );
+
+    let home = AdvancedVolumes::parse_short("~/config:/config:rshared").unwrap();
+    assert_eq!(home._type, "bind");
+    assert_eq!(home.bind.unwrap().propagation.as_deref(), Some("rshared"));
+
+    let windows = AdvancedVolumes::parse_short(r"C:\data:/data").unwrap();
+    assert_eq!(windows._type, "bind");
+    assert_eq!(windows.source.as_deref(), Some(r"C:\data"));
+    assert_eq!(windows.target, "/data");
+    let windows = AdvancedVolumes::parse_short(r"D:/src:C:\app:ro").unwrap();
+    assert_eq!(windows.source.as_deref(), Some("D:/src"));
+    assert_eq!(windows.target, r"C:\app");
+    assert!(windows.read_only);
+
+    let named = AdvancedVolumes::parse_short("db-data:/var/lib/postgresql:nocopy").unwrap();
+    assert_eq!(named._type, "volume");
+    assert_eq!(named.source.as_deref(), Some("db-data"));
+    assert_eq!(
+        named.volume,
+        Some(Volume {
+            nocopy: Some(true),
+            subpath: None,
+        })
+    );
+
+    let anonymous = AdvancedVolumes::parse_short("/var/cache").unwrap();
+    assert_eq!(anonymous._type, "volume");
+    assert_eq!(anonymous.source, None);
+    assert_eq!(anonymous.target, "/var/cache");
+
+    for invalid in [
+        "",
+        "data:",
+        "./a:/b:ro:z",
+        "data:/data:z",
+        "./a:/b:nocopy",
+        "data:/b:rx",
+        "/data:ro",
+        "data:relative/path",
+    ] {
+        assert!(AdvancedVolumes::parse_short(invalid).is_err(), "{invalid}");
+    }
+    assert_eq!(
+        AdvancedVolumes::parse_short("data:/data:z")
+            .unwrap_err()
+            .to_string(),
+        "invalid volume \"data:/data:z\": \"z\" is not supported for this mount type"
+    );
+
+    for spec in [
+        "./data:/data:ro,z",
+        "~/config:/config:rshared",
+        r"C:\data:/data",
+        "db-data:/var/lib/postgresql:nocopy",
+        "/var/cache",
+    ] {
+        let volume = AdvancedVolumes::parse_short(spec).unwrap();
+        assert_eq!(volume.to_short().as_deref(), Some(spec));
+    }
+    let mut subpath = named.clone();
+    subpath.volume = Some(Volume {
+        nocopy: None,
+        subpath: Some("sub".to_string()),
+    });
+    assert_eq!(subpath.to_short(), None);
+
+    assert_eq!(
+        Volumes::Simple("db-data:/var/lib/postgresql:nocopy".to_string()).normalize(),
+        Ok(named.clone())
+    );
+    assert_eq!(Volumes::Advanced(named.clone()).normalize(), Ok(named));
+}
+
+#[test]
+fn parse_durations() {
+    use docker_compose_types::{ComposeDuration, Healthcheck, Service};
+    use std::time::Duration;
+
+    let parse = |s: &str| {
+        s.parse::<ComposeDuration>()
+            .map(|d| d.as_duration().unwrap())
+    };
+    assert_eq!(parse("1m30s"), Ok(Duration::from_secs(90)));
+    assert_eq!(parse("500ms"), Ok(Duration::from_millis(500)));
+    assert_eq!(parse("1h"), Ok(Duration::from_secs(3600)));
+    assert_eq!(parse("1.5h"), Ok(Duration::from_secs(5400)));
+    assert_eq!(parse(".5s"), Ok(Duration::from_millis(500)));
+    assert_eq!(parse("2h45m3s250us"), Ok(Duration::new(9903, 250_000)));
+    assert_eq!(parse("10µs"), Ok(Duration::from_micros(10)));
+    assert_eq!(parse("0"), Ok(Duration::ZERO));
+
+    for invalid in ["", "10", "s", "1x", "-1s", "1.s.5", "1h30"] {
+        assert!(parse(invalid).is_err(), "{invalid}");
+    }
+    assert_eq!(
+        parse("10").unwrap_err().to_string(),
+        "invalid duration \"10\": missing unit"
+    );
+
+    for (input, canonical) in [
+        ("90s", "1m30s"),
+        ("1.5s", "1s500ms"),
+        ("1h0m0s", "1h"),
+        ("0s", "0s"),
+        ("3723001001001ns", "1h2m3s1ms1us1ns"),
+    ] {
+        assert_eq!(
+            input.parse::<ComposeDuration>().unwrap().to_string(),
+            canonical
+        );
+    }
+
+    let healthcheck: Healthcheck = from_str("interval: 90s\ntimeout: 2.5s\n").unwrap();
+    assert_eq!(
+        healthcheck.interval.as_ref().and_then(|d| d.as_duration()),
+        Some(Duration::from_secs(90))
+    );
+    let yaml = to_string(&healthcheck).unwrap();
+    let fields: std::collections::BTreeMap<String, String> = from_str(&yaml).unwrap();
+    assert_eq!(fields["interval"], "1m30s", "{yaml}");
+    assert_eq!(fields["timeout"], "2s500ms", "{yaml}");
+
+    // Durations referencing variables are kept until they are interpolated
+    let healthcheck: Healthcheck =
+        from_str("interval: ${INTERVAL:-30s}\ntimeout: $TIMEOUT\n").unwrap();
+    let interval = healthcheck.interval.as_ref().unwrap();
+    assert_eq!(interval.as_duration(), None);
+    assert_eq!(interval.to_string(), "${INTERVAL:-30s}");
+    let round_trip: Healthcheck = from_str(&to_string(&healthcheck).unwrap()).unwrap();
+    assert_eq!(round_trip, healthcheck);
+    let service: Service = from_str("stop_grace_period: ${G:-10s}\n").unwrap();
+    assert_eq!(service.stop_grace_period.unwrap().as_duration(), None);
+
+    let error = from_str::<Healthcheck>("interval: soon\n").unwrap_err();
+    assert!(
+        error.to_string().contains("invalid duration \"soon\""),
+        "{error}"
+    );
+}
+
+#[test]
+fn parse_byte_sizes() {
+    use docker_compose_types::{
+        AdvancedBuildStep, BuildStep, ByteSize, ComposeFile, Service, TmpfsSettings,
+    };
+
+    let bytes = |s: &str| s.parse::<ByteSize>().map(|size| size.as_bytes().unwrap());
+    assert_eq!(bytes("512"), Ok(512));
+    assert_eq!(bytes("512b"), Ok(512));
+    assert_eq!(bytes("2k"), Ok(2048));
+    assert_eq!(bytes("2KB"), Ok(2048));
+    assert_eq!(bytes("300M"), Ok(300 * 1024 * 1024));
+    assert_eq!(bytes("1gb"), Ok(1 << 30));
+    assert_eq!(bytes("0.5G"), Ok(1 << 29));
+    assert_eq!(bytes("1.5 mib"), Ok(3 << 19));
+    for invalid in ["", "g", "1x", "1.g", "-1m", "1ib", "1 2m"] {
+        assert!(bytes(invalid).is_err(), "{invalid}");
+    }
+
+    let file: ComposeFile = from_str(
+        r#"
+services:
+  app:
+    image: busybox
+    mem_limit: 0.5G
+    shm_size: 67108864
+    build:
+      context: .
+      shm_size: '2gb'
+    volumes:
+      - type: tmpfs
+        target: /tmp
+        tmpfs:
+          size: 10m
+    deploy:
+      resources:
+        limits:
+          memory: 100M
+"#,
+    )
+    .unwrap();
+    let ComposeFile::V2Plus(compose) = &file else {
+        panic!("expected a v2+ file");
+    };
+    let app = compose.services.0["app"].as_ref().unwrap();
+    assert_eq!(
+        app.mem_limit.as_ref().and_then(ByteSize::as_bytes),
+        Some(1 << 29)
+    );
+    assert_eq!(app.shm_size, Some(ByteSize::new(64 << 20)));
+    let Some(BuildStep::Advanced(AdvancedBuildStep { shm_size, .. })) = &app.build_ else {
+        panic!("expected an advanced build");
+    };
+    assert_eq!(
+        shm_size.as_ref().and_then(ByteSize::as_bytes),
+        Some(2 << 30)
+    );
+    let tmpfs = match &app.volumes[0] {
+        docker_compose_types::Volumes::Advanced(volume) => volume.tmpfs.clone(),
+        _ => None,
+    };
+    assert_eq!(
+        tmpfs.and_then(|t: TmpfsSettings| t.size.as_bytes()),
+        Some(10 << 20)
+    );
+
+    // Sizes serialize the way they were written
+    let yaml = to_string(&file).unwrap();
+    for (key, value) in [
+        ("mem_limit", "0.5G"),
+        ("shm_size", "67108864"),
+        ("shm_size", "2gb"),
+        ("size", "10m"),
+        ("memory", "100M"),
+    ] {
+        assert!(
+            yaml.contains(&format!("{key}: {value}\n"))
+                || yaml.contains(&format!("{key}: '{value}'\n")),
+            "{key}: {value} in {yaml}"
+        );
+    }
+
+    // Sizes compare by their number of bytes, whatever the notation
+    let size = |s: &str| s.parse::<ByteSize>().unwrap();
+    assert_eq!(size("1g"), size("1024m"));
+    assert_eq!(size("1g"), ByteSize::new(1 << 30));
+    assert_ne!(size("1g"), size("1023m"));
+    let sizes: std::collections::HashSet<ByteSize> =
+        [size("1g"), size("1024m"), ByteSize::new(1 << 30)]
+            .into_iter()
+            .collect();
+    assert_eq!(sizes.len(), 1);
+
+    // Sizes referencing variables are kept until they are interpolated
+    let app: Service = from_str(
+        r#"
+mem_limit: ${MEM:-512m}
+memswap_limit: $SWAP
+blkio_config:
+  device_read_bps:
+    - path: /dev/sda
+      rate: ${RATE}
+"#,
+    )
+    .unwrap();
+    let mem_limit = app.mem_limit.as_ref().unwrap();
+    assert_eq!(mem_limit.as_bytes(), None);
+    assert_eq!(mem_limit.to_string(), "${MEM:-512m}");
+    assert_eq!(
+        app.memswap_limit
+            .as_ref()
+            .map(ToString::to_string)
+            .as_deref(),
+        Some("$SWAP")
+    );
+    let round_trip: Service = from_str(&to_string(&app).unwrap()).unwrap();
+    assert_eq!(round_trip, app);
+}
+
+#[test]
+fn parse_image_refs() {
+    use docker_compose_types::{ImageRef, Service};
+
+    let nginx = ImageRef::parse("nginx").unwrap();
+    assert_eq!(nginx.registry, None);
+    assert_eq!(nginx.registry(), "docker.io");
+    assert_eq!(nginx.namespace(), Some("library"));
+    assert_eq!(nginx.repository(), "nginx");
+    assert_eq!(
+        nginx.normalize().to_string(),
+        "docker.io/library/nginx:latest"
+    );
+    assert_eq!(nginx.to_string(), "nginx");
+
+    let image = ImageRef::parse("localhost:5000/team/app/api:1.2.3").unwrap();
+    assert_eq!(image.registry.as_deref(), Some("localhost:5000"));
+    assert_eq!(image.registry_host(), "localhost");
+    assert_eq!(image.registry_port(), Some(5000));
+    assert_eq!(image.namespace(), Some("team/app"));
+    assert_eq!(image.repository(), "api");
+    assert_eq!(image.tag.as_deref(), Some("1.2.3"));
+    assert_eq!(image.normalize(), image);
+
+    let digest = "sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+    let pinned = ImageRef::parse(&format!("ghcr.io/org/tool@{digest}")).unwrap();
+    assert!(pinned.is_pinned_by_digest());
+    assert_eq!(pinned.tag, None);
+    assert_eq!(pinned.normalize().tag, None);
+    assert_eq!(pinned.registry_port(), None);
+    let retagged = pinned.with_tag("v2");
+    assert!(!retagged.is_pinned_by_digest());
+    assert_eq!(retagged.to_string(), "ghcr.io/org/tool:v2");
+
+    let legacy = ImageRef::parse("index.docker.io/redis:7").unwrap();
+    assert_eq!(legacy.normalize().to_string(), "docker.io/library/redis:7");
+    assert_eq!(
+        "bitnami/redis"
+            .parse::<ImageRef>()
+            .unwrap()
+            .normalize()
+            .to_string(),
+        "docker.io/bitnami/redis:latest"
+    );
+
+    for invalid in [
+        "",
+        "Nginx",
+        "nginx:",
+        "nginx:-bad",
+        "nginx@sha256:short",
+        "org//app",
+        "my_registry.io:port/app",
+    ] {
+        assert!(ImageRef::parse(invalid).is_err(), "{invalid}");
+    }
+
+    let service = Service {
+        image: Some("postgres:16-alpine".to_string()),
+        ..Default::default()
+    };
+    let image = service.image_ref().unwrap().unwrap();
+    assert_eq!(image.tag.as_deref(), Some("16-alpine"));
+    assert!(Service::default().image_ref().is_none());
+}
+
+#[test]
+fn normalize_environment() {
+    use docker_compose_types::{Environment, EnvironmentMap};
+    use std::collections::HashMap;
+
+    let list: Environment = from_str("[B=2, EMPTY=, HOST, A=x=y, B=3]").unwrap();
+    let kv: Environment = from_str("{B: 3, EMPTY: '', HOST: null, A: x=y}").unwrap();
+    let env = list.normalize();
+    assert_eq!(env, kv.normalize());
+    assert_eq!(
+        env.iter().collect::<Vec<_>>(),
+        [
+            ("B", Some("3")),
+            ("EMPTY", Some("")),
+            ("HOST", None),
+            ("A", Some("x=y"))
+        ]
+    );
+    assert_eq!(env.get("EMPTY"), Some(Some("")));
+    assert!(env.is_inherited("HOST"));
+    assert!(!env.is_inherited("EMPTY"));
+    assert_eq!(env.get("MISSING"), None);
+
+    let host: HashMap<String, String> = [("HOST".to_string(), "h".to_string())].into();
+    let resolved = env.resolve(&host);
+    assert_eq!(resolved.get("HOST"), Some(Some("h")));
+    assert_eq!(resolved.len(), 4);
+    assert!(!env.resolve(&HashMap::new()).contains_key("HOST"));
+
+    assert_eq!(
+        env.to_list(),
+        Environment::List(vec![
+            "B=3".to_string(),
+            "EMPTY=".to_string(),
+            "HOST".to_string(),
+            "A=x=y".to_string(),
+        ])
+    );
+    assert_eq!(env.to_kv_pair().normalize(), env);
+    assert_eq!(env.to_list().normalize(), env);
+
+    let mut env: EnvironmentMap = env;
+    env.insert("B", None);
+    env.insert("C", Some("c".to_string()));
+    assert_eq!(env.remove("EMPTY"), Some(Some(String::new())));
+    let keys: Vec<_> = env.iter().map(|(k, _)| k).collect();
+    assert_eq!(keys, ["B", "HOST", "A", "C"]);
+}
+
+#[test]
+fn effective_service_environment() {
+    use docker_compose_types::{ComposeLoader, EnvSource, InheritedFrom};
+    use std::collections::HashMap;
+
+    let host: HashMap<String, String> = [
+        ("API_TOKEN".to_string(), "from-host".to_string()),
+        ("SHELL_USER".to_string(), "alice".to_string()),
+    ]
+    .into();
+    let project = ComposeLoader::new()
+        .file("tests/fixtures/service-environment/docker-compose.yml")
+        .variables(host.clone())
+        .load()
+        .unwrap();
+    let web = project.compose.services.0["web"].as_ref().unwrap();
+    let env = project.service_environment(web, &host).unwrap();
+
+    let common = EnvSource::EnvFile(project.resolve_path("common.env"));
+    let web_env = EnvSource::EnvFile(project.resolve_path("web.env"));
+    let summary: Vec<_> = env
+        .iter()
+        .map(|v| (v.name.as_str(), v.value.as_str(), v.inherited_from))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("LOG_LEVEL", "debug", None),
+            ("DATABASE_URL", "postgres://db/eu-west-1", None),
+            ("PORT", "8080", None),
+            ("SHELL_USER", "alice", Some(InheritedFrom::Host)),
+            ("API_TOKEN", "from-host", Some(InheritedFrom::Host)),
+            ("REGION", "eu-west-1", Some(InheritedFrom::Dotenv)),
+        ]
+    );
+    assert_eq!(env[0].source, EnvSource::Environment);
+    assert_eq!(env[1].source, common);
+    assert_eq!(env[2].source, web_env);
+    assert_eq!(env[2].overrides, [common]);
+    assert_eq!(env[0].overrides, env[2].overrides);
+    assert_eq!(env[5].source, EnvSource::Environment);
+
+    // Without the host variables, pass-through falls back to the .env file
+    let env = project.service_environment(web, &HashMap::new()).unwrap();
+    let token = env.iter().find(|v| v.name == "API_TOKEN").unwrap();
+    assert_eq!(token.value, "from-dotenv");
+    assert_eq!(token.inherited_from, Some(InheritedFrom::Dotenv));
+    assert!(!env.iter().any(|v| v.name == "SHELL_USER"));
+}
+
+#[test]
+fn configs() {
+    use docker_compose_types::{AdvancedConfigs, Compose, ComposeConfig, Configs, Labels, Service};
+
+    let yaml = std::fs::read_to_string("tests/fixtures/configs/docker-compose.yml").unwrap();
+    let compose: Compose = from_str(&yaml).unwrap();
+    let configs = &compose.configs.as_ref().unwrap().0;
+    assert_eq!(
+        configs["nginx"].as_ref().unwrap().file.as_deref(),
+        Some("./nginx.conf")
+    );
+    assert!(matches!(
+        &configs["nginx"].as_ref().unwrap().labels,
+        Labels::Map(labels) if labels["com.example.role"] == "proxy"
+    ));
+    assert_eq!(
+        configs["settings"].as_ref().unwrap().environment.as_deref(),
+        Some("APP_SETTINGS")
+    );
+    let greeting = configs["greeting"].as_ref().unwrap();
+    assert_eq!(greeting.content.as_deref(), Some("hello world\n"));
+    assert_eq!(greeting.template_driver.as_deref(), Some("golang"));
+    assert_eq!(
+        configs["shared"],
+        Some(ComposeConfig {
+            external: true,
+            name: Some("shared_config".to_string()),
+            ..Default::default()
+        })
+    );
+
+    let services = &compose.services.0;
+    let web = services["web"].as_ref().unwrap();
+    assert_eq!(
+        web.configs,
+        Some(Configs::Advanced(vec![AdvancedConfigs {
+            source: "nginx".to_string(),
+            target: Some("/etc/nginx/nginx.conf".to_string()),
+            uid: Some("101".to_string()),
+            gid: Some("101".to_string()),
+            mode: Some("0440".to_string()),
+        }]))
+    );
+    let worker = services["worker"].as_ref().unwrap();
+    assert_eq!(
+        worker.configs,
+        Some(Configs::Simple(vec![
+            "settings".to_string(),
+            "greeting".to_string(),
+            "shared".to_string()
+        ]))
+    );
+    assert!(compose.validate().is_empty());
+    let round_trip: Compose = from_str(&to_string(&compose).unwrap()).unwrap();
+    assert_eq!(round_trip.configs, compose.configs);
+
+    // Configs are merged by target, short entries target `/<source>`
+    let merged = worker.clone().merge(Service {
+        configs: Some(Configs::Advanced(vec![
+            AdvancedConfigs {
+                source: "greeting".to_string(),
+                target: Some("/greeting".to_string()),
+                mode: Some("0400".to_string()),
+                ..Default::default()
+            },
+            AdvancedConfigs {
+                source: "settings".to_string(),
+                target: Some("settings".to_string()),
+                uid: Some("1000".to_string()),
+                ..Default::default()
+            },
+            AdvancedConfigs {
+                source: "extra".to_string(),
+                ..Default::default()
+            },
+        ])),
+        ..Default::default()
+    });
+    let Some(Configs::Advanced(merged)) = merged.configs else {
+        panic!("expected long syntax configs");
+    };
+    let sources: Vec<_> = merged.iter().map(|c| c.source.as_str()).collect();
+    assert_eq!(sources, ["settings", "greeting", "shared", "extra"]);
+    assert_eq!(merged[0].uid.as_deref(), Some("1000"));
+    assert_eq!(merged[1].mode.as_deref(), Some("0400"));
+
+    let mut missing = compose.clone();
+    missing
+        .configs
+        .as_mut()
+        .unwrap()
+        .0
+        .retain(|name, _| name != "shared");
+    let diagnostics = missing.validate();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].to_string(),
+        "error: services.worker.configs[2]: config \"shared\" is not defined"
+    );
+}
+
+#[test]
+fn full_secret_definitions() {
+    use docker_compose_types::{Compose, ComposeSecret, Labels, SingleValue};
+
+    let yaml = std::fs::read_to_string("tests/fixtures/secrets/full-secret.yml").unwrap();
+    let compose: Compose = from_str(&yaml).unwrap();
+    let secrets = &compose.secrets.as_ref().unwrap().0;
+    assert_eq!(
+        secrets["db_password"],
+        Some(ComposeSecret {
+            external: true,
+            ..Default::default()
+        })
+    );
+    let api_token = secrets["api_token"].as_ref().unwrap();
+    assert_eq!(api_token.content.as_deref(), Some("not-so-secret"));
+    assert!(matches!(
+        &api_token.labels,
+        Labels::Map(labels) if labels["com.example.scope"] == "api"
+    ));
+    let tls_key = secrets["tls_key"].as_ref().unwrap();
+    assert_eq!(tls_key.name.as_deref(), Some("tls_key_v2"));
+    assert_eq!(tls_key.driver.as_deref(), Some("vault"));
+    assert_eq!(
+        tls_key.driver_opts["path"],
+        Some(SingleValue::String("secret/tls".to_string()))
+    );
+    assert_eq!(
+        tls_key.driver_opts["ttl"],
+        Some(SingleValue::Unsigned(3600))
+    );
+    let rendered = secrets["rendered"].as_ref().unwrap();
+    assert_eq!(rendered.file.as_deref(), Some("./templates/rendered.tmpl"));
+    assert_eq!(rendered.template_driver.as_deref(), Some("golang"));
+
+    assert!(compose.validate().is_empty());
+    let round_trip: Compose = from_str(&to_string(&compose).unwrap()).unwrap();
+    assert_eq!(round_trip.secrets, compose.secrets);
+}
+
+#[test]
+fn develop_watch() {
+    use docker_compose_types::{Command, Compose, Develop, WatchAction};
+
+    let yaml = std::fs::read_to_string("tests/fixtures/develop-watch/docker-compose.yml").unwrap();
+    let compose: Compose = from_str(&yaml).unwrap();
+    let web = compose.services.0["web"].as_ref().unwrap();
+    let watch = &web.develop.as_ref().unwrap().watch;
+    let actions: Vec<_> = watch.iter().map(|rule| rule.action).collect();
+    assert_eq!(
+        actions,
+        [
+            WatchAction::Sync,
+            WatchAction::Rebuild,
+            WatchAction::SyncRestart,
+            WatchAction::SyncExec,
+            WatchAction::Restart,
+        ]
+    );
+    assert_eq!(watch[0].path, "./web");
+    assert_eq!(watch[0].target.as_deref(), Some("/src/web"));
+    assert!(watch[0].initial_sync);
+    assert_eq!(watch[0].ignore, ["node_modules/"]);
+    assert!(!watch[1].initial_sync);
+    assert_eq!(watch[3].include, ["*.scss"]);
+    let exec = watch[3].exec.as_ref().unwrap();
+    assert_eq!(
+        exec.command,
+        Command::Args(vec![
+            "npm".to_string(),
+            "run".to_string(),
+            "build:css".to_string()
+        ])
+    );
+    assert_eq!(exec.user.as_deref(), Some("node"));
+    assert_eq!(exec.working_dir.as_deref(), Some("/src"));
+    assert!(!exec.environment.is_empty());
+
+    let serialized = to_string(&compose).unwrap();
+    assert!(serialized.contains("action: sync+restart"));
+    let round_trip: Compose = from_str(&serialized).unwrap();
+    assert_eq!(round_trip, compose);
+
+    assert!(from_str::<Develop>("watch:\n  - action: copy\n    path: .\n").is_err());
+}
+
+#[test]
+fn full_build_section() {
+    use docker_compose_types::{
+        AdditionalContexts, AdvancedBuildStep, BuildStep, Compose, Secrets, Service, SshConfig,
+    };
+
+    let yaml = std::fs::read_to_string("tests/fixtures/build-full/docker-compose.yml").unwrap();
+    let compose: Compose = from_str(&yaml).unwrap();
+    let build = |name: &str| match &compose.services.0[name].as_ref().unwrap().build_ {
+        Some(BuildStep::Advanced(build)) => build.clone(),
+        other => panic!("expected a long build section, got {other:?}"),
+    };
+    let app = build("app");
+    assert!(app.context.is_empty());
+    assert_eq!(app.dockerfile.as_deref(), Some("Dockerfile.app"));
+    assert_eq!(
+        app.ssh,
+        Some(SshConfig::List(vec![
+            "default".to_string(),
+            "deploy=~/.ssh/deploy_key".to_string()
+        ]))
+    );
+    assert!(
+        matches!(&app.secrets, Some(Secrets::Advanced(secrets)) if secrets[0].source == "npm_token")
+    );
+    assert_eq!(app.tags, ["registry.example.com/app:latest"]);
+    assert_eq!(app.platforms, ["linux/amd64", "linux/arm64"]);
+    assert!(matches!(
+        &app.additional_contexts,
+        Some(AdditionalContexts::Map(contexts)) if contexts["resources"] == "../resources"
+    ));
+    assert_eq!(app.cache_to, ["type=inline"]);
+    assert_eq!(app.extra_hosts, ["mirror.local:10.0.0.5"]);
+    assert_eq!(app.isolation.as_deref(), Some("default"));
+    assert!(app.no_cache && app.pull && app.privileged);
+    assert!(app.ulimits.0.contains_key("nofile"));
+    assert_eq!(app.entitlements, ["network.host"]);
+    assert_eq!(build("tool").context, "./tool");
+
+    let round_trip: Compose = from_str(&to_string(&compose).unwrap()).unwrap();
+    assert_eq!(round_trip, compose);
+    assert!(!to_string(&app)
+        .unwrap()
+        .lines()
+        .any(|line| line.starts_with("context:")));
+
+    let diagnostics = compose.validate();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].to_string(),
+        "error: services.tool.build.secrets[1]: secret \"github_token\" is not defined"
+    );
+
+    let base = Service {
+        build_: Some(BuildStep::Advanced(app)),
+        ..Default::default()
+    };
+    let merged = base.merge(Service {
+        build_: Some(BuildStep::Advanced(AdvancedBuildStep {
+            context: "./app".to_string(),
+            tags: vec!["app:dev".to_string()],
+            additional_contexts: Some(AdditionalContexts::List(vec![
+                "resources=./resources".to_string()
+            ])),
+            ..Default::default()
+        })),
+        ..Default::default()
+    });
+    let Some(BuildStep::Advanced(merged)) = merged.build_ else {
+        panic!("expected a long build section");
+    };
+    assert_eq!(merged.context, "./app");
+    assert_eq!(merged.dockerfile.as_deref(), Some("Dockerfile.app"));
+    assert_eq!(merged.tags, ["registry.example.com/app:latest", "app:dev"]);
+    assert!(matches!(
+        &merged.additional_contexts,
+        Some(AdditionalContexts::Map(contexts))
+            if contexts["resources"] == "./resources" && contexts.len() == 2
+    ));
+    assert!(merged.no_cache);
+}
+
+#[test]
+fn resource_tuning_fields() {
+    use docker_compose_types::{ByteSize, Compose, Cpus, MemswapLimit, Service, StringOrUnsigned};
+
+    let yaml =
+        std::fs::read_to_string("tests/fixtures/resource-tuning/docker-compose.yml").unwrap();
+    let compose: Compose = from_str(&yaml).unwrap();
+    let db = compose.services.0["db"].as_ref().unwrap();
+    assert_eq!(db.cpus, Some(Cpus::Number(1.5)));
+    assert_eq!(db.cpu_shares, Some(512));
+    assert_eq!(db.cpu_quota, Some(50000));
+    assert_eq!(db.cpu_period, Some(100000));
+    assert_eq!(db.cpuset.as_deref(), Some("0-3"));
+    assert_eq!(
+        db.cpu_rt_runtime,
+        Some(StringOrUnsigned::String("400ms".to_string()))
+    );
+    assert_eq!(db.cpu_rt_period, Some(StringOrUnsigned::Unsigned(1400)));
+    assert_eq!(
+        db.memswap_limit,
+        Some(MemswapLimit::Limited("3g".parse().unwrap()))
+    );
+    assert!(db.oom_kill_disable);
+    assert_eq!(db.oom_score_adj, Some(-500));
+    assert_eq!(db.pids_limit, Some(200));
+
+    let blkio = db.blkio_config.as_ref().unwrap();
+    assert_eq!(blkio.weight, Some(300));
+    assert_eq!(blkio.weight_device[0].weight, 400);
+    assert_eq!(
+        blkio.device_read_bps[0].rate.as_bytes(),
+        Some(12 * 1024 * 1024)
+    );
+    assert_eq!(blkio.device_write_bps[0].rate, ByteSize::new(1048576));
+    assert_eq!(blkio.device_read_iops[0].rate, 120);
+    assert_eq!(blkio.device_write_iops[0].path, "/dev/sda");
+
+    let windows = compose.services.0["windows"].as_ref().unwrap();
+    assert_eq!(windows.cpus, Some(Cpus::Number(0.5)));
+    assert_eq!(windows.cpu_count, Some(2));
+    assert_eq!(windows.cpu_percent, Some(50));
+    assert_eq!(windows.memswap_limit, Some(MemswapLimit::Unlimited));
+    assert_eq!(windows.pids_limit, Some(-1));
+
+    let serialized = to_string(&compose).unwrap();
+    assert!(serialized.contains("memswap_limit: -1"));
+    let round_trip: Compose = from_str(&serialized).unwrap();
+    assert_eq!(round_trip, compose);
+    assert!(from_str::<Service>("cpus: lots").is_err());
+    let service: Service = from_str("cpus: ${CPUS:-1}").unwrap();
+    assert_eq!(
+        service.cpus,
+        Some(Cpus::Unresolved("${CPUS:-1}".to_string()))
+    );
+    assert_eq!(service.cpus.unwrap().as_f64(), None);
+
+    let merged = db.clone().merge(
+        from_str(
+            r#"
+cpus: 2
+blkio_config:
+  device_read_bps:
+    - path: /dev/sda
+      rate: 20mb
+    - path: /dev/sdb
+      rate: 5mb
+"#,
+        )
+        .unwrap(),
+    );
+    assert_eq!(merged.cpus.and_then(|cpus| cpus.as_f64()), Some(2.0));
+    assert_eq!(merged.cpu_shares, Some(512));
+    let blkio = merged.blkio_config.unwrap();
+    assert_eq!(blkio.weight, Some(300));
+    let rates: Vec<_> = blkio
+        .device_read_bps
+        .iter()
+        .map(|d| (d.path.as_str(), d.rate.to_string()))
+        .collect();
+    assert_eq!(
+        rates,
+        [
+            ("/dev/sda", "20mb".to_string()),
+            ("/dev/sdb", "5mb".to_string())
+        ]
+    );
+}
+
+#[test]
+fn full_deploy_section() {
+    use docker_compose_types::{Compose, DiscreteResourceSpec, Labels};
+    use std::time::Duration;
+
+    let yaml = std::fs::read_to_string("tests/fixtures/deploy-full/docker-compose.yml").unwrap();
+    let compose: Compose = from_str(&yaml).unwrap();
+    let deploy = |name: &str| {
+        compose.services.0[name]
+            .as_ref()
+            .unwrap()
+            .deploy
+            .clone()
+            .unwrap()
+    };
+    let api = deploy("api");
+    assert_eq!(api.endpoint_mode.as_deref(), Some("dnsrr"));
+    assert!(matches!(&api.labels, Labels::Map(labels) if labels["com.example.tier"] == "backend"));
+    let update = api.update_config.as_ref().unwrap();
+    assert_eq!(update.order.as_deref(), Some("start-first"));
+    assert_eq!(
+        update.delay.as_ref().and_then(|d| d.as_duration()),
+        Some(Duration::from_secs(10))
+    );
+    let rollback = api.rollback_config.as_ref().unwrap();
+    assert_eq!(rollback.parallelism, Some(0));
+    assert_eq!(rollback.failure_action.as_deref(), Some("pause"));
+    assert_eq!(rollback.max_failure_ratio, Some(0.2));
+    assert_eq!(rollback.order.as_deref(), Some("stop-first"));
+    assert_eq!(
+        api.placement.as_ref().unwrap().max_replicas_per_node,
+        Some(2)
+    );
+    let resources = api.resources.as_ref().unwrap();
+    assert_eq!(resources.limits.as_ref().unwrap().pids, Some(100));
+    let reservations = resources.reservations.as_ref().unwrap();
+    assert_eq!(
+        reservations.generic_resources[0].discrete_resource_spec,
+        Some(DiscreteResourceSpec {
+            kind: "gpu".to_string(),
+            value: 2
+        })
+    );
+    assert!(
+        matches!(&deploy("worker").labels, Labels::List(labels) if labels == &["com.example.tier=jobs"])
+    );
+
+    let round_trip: Compose = from_str(&to_string(&compose).unwrap()).unwrap();
+    assert_eq!(round_trip, compose);
+}
+
+#[test]
+fn network_attachment_settings() {
+    use docker_compose_types::{Compose, MapOrEmpty, Networks, Service};
+
+    let yaml =
+        std::fs::read_to_string("tests/fixtures/network-attachments/docker-compose.yml").unwrap();
+    let compose: Compose = from_str(&yaml).unwrap();
+    let router = compose.services.0["router"].as_ref().unwrap();
+    assert_eq!(router.mac_address.as_deref(), Some("02:42:ac:11:00:02"));
+    let networks = router.networks.normalize();
+    let MapOrEmpty::Map(front) = &networks.0["front"] else {
+        panic!("expected settings for the front network");
+    };
+    assert_eq!(front.priority, Some(1000));
+    assert_eq!(front.gw_priority, Some(1));
+    assert_eq!(front.link_local_ips, ["57.123.22.11"]);
+    assert_eq!(front.mac_address.as_deref(), Some("02:42:ac:11:65:43"));
+    assert_eq!(front.interface_name.as_deref(), Some("eth-front"));
+    assert_eq!(front.driver_opts["mtu"].to_string(), "1400");
+    assert_eq!(networks.0.keys().collect::<Vec<_>>(), ["front", "back"]);
+
+    let worker = compose.services.0["worker"].as_ref().unwrap();
+    assert!(matches!(worker.networks, Networks::Simple(_)));
+    let normalized = worker.networks.normalize();
+    assert_eq!(normalized.0.keys().collect::<Vec<_>>(), ["back", "front"]);
+    assert!(normalized.0.values().all(|n| *n == MapOrEmpty::Empty));
+    assert_eq!(
+        Networks::Advanced(normalized.clone()).normalize(),
+        normalized
+    );
+
+    assert!(compose.validate().is_empty());
+    let round_trip: Compose = from_str(&to_string(&compose).unwrap()).unwrap();
+    assert_eq!(round_trip, compose);
+
+    // Settings for the same network are merged field by field
+    let merged = from_str::<Service>(
+        r#"
+networks:
+  front:
+    aliases: [a]
+    ipv4_address: 10.0.0.2
+    driver_opts:
+      mtu: 1400
+"#,
+    )
+    .unwrap()
+    .merge(
+        from_str(
+            r#"
+networks:
+  front:
+    aliases: [a, b]
+    priority: 5
+    driver_opts:
+      com.example.opt: "on"
+"#,
+        )
+        .unwrap(),
+    );
+    let networks = merged.networks.normalize();
+    let MapOrEmpty::Map(front) = &networks.0["front"] else {
+        panic!("expected settings for the front network");
+    };
+    assert_eq!(front.aliases, ["a", "b"]);
+    assert_eq!(front.ipv4_address.as_deref(), Some("10.0.0.2"));
+    assert_eq!(front.priority, Some(5));
+    assert_eq!(front.driver_opts.len(), 2);
+}
+
+#[test]
+fn network_ipam_options() {
+    use docker_compose_types::{Compose, ComposeNetwork, MapOrEmpty};
+
+    let yaml = std::fs::read_to_string("tests/fixtures/network-ipam/docker-compose.yml").unwrap();
+    let compose: Compose = from_str(&yaml).unwrap();
+    let network = |name: &str| match &compose.networks.0[name] {
+        MapOrEmpty::Map(settings) => settings.clone(),
+        MapOrEmpty::Empty => panic!("expected settings for {name}"),
+    };
+    let lab = network("lab");
+    assert_eq!(lab.enable_ipv4, Some(true));
+    assert!(lab.enable_ipv6);
+    let ipam = lab.ipam.unwrap();
+    assert_eq!(ipam.options["foo"], "bar");
+    assert_eq!(ipam.config[0].subnet.as_deref(), Some("10.5.0.0/16"));
+    assert_eq!(ipam.config[0].ip_range.as_deref(), Some("10.5.0.0/24"));
+    assert_eq!(ipam.config[0].gateway.as_deref(), Some("10.5.0.1"));
+    assert_eq!(ipam.config[0].aux_addresses["host2"], "10.5.0.3");
+    assert_eq!(ipam.config[1].subnet.as_deref(), Some("2001:db8::/64"));
+
+    let Some(ComposeNetwork::Detailed(legacy)) = network("legacy").external else {
+        panic!("expected external details");
+    };
+    assert_eq!(legacy.name.as_deref(), Some("corp_legacy"));
+    assert_eq!(legacy.extensions.len(), 1);
+    assert_eq!(network("shared").external, Some(ComposeNetwork::Bool(true)));
+
+    let ipv6only = network("ipv6only");
+    assert_eq!(ipv6only.enable_ipv4, Some(false));
+    let ipam = ipv6only.ipam.unwrap();
+    assert_eq!(ipam.config[0].subnet, None);
+    assert_eq!(ipam.config[0].gateway.as_deref(), Some("2001:db8:1::1"));
+
+    assert!(compose.validate().is_empty());
+    let round_trip: Compose = from_str(&to_string(&compose).unwrap()).unwrap();
+    assert_eq!(round_trip, compose);
+
+    // IPAM settings are merged, `config` entries are appended
+    let merged = from_str::<Compose>(
+        r#"
+networks:
+  lab:
+    ipam:
+      config:
+        - subnet: 10.0.0.0/24
+      options:
+        foo: bar
+"#,
+    )
+    .unwrap()
+    .merge(
+        from_str(
+            r#"
+networks:
+  lab:
+    ipam:
+      driver: default
+      config:
+        - subnet: 10.0.0.0/24
+        - subnet: 10.0.1.0/24
+      options:
+        baz: qux
+"#,
+        )
+        .unwrap(),
+    );
+    let MapOrEmpty::Map(lab) = &merged.networks.0["lab"] else {
+        panic!("expected settings for lab");
+    };
+    let ipam = lab.ipam.as_ref().unwrap();
+    assert_eq!(ipam.driver.as_deref(), Some("default"));
+    let subnets: Vec<_> = ipam
+        .config
+        .iter()
+        .filter_map(|c| c.subnet.as_deref())
+        .collect();
+    assert_eq!(subnets, ["10.0.0.0/24", "10.0.1.0/24"]);
+    assert_eq!(ipam.options.len(), 2);
+}
+
+#[test]
+fn lifecycle_hooks() {
+    use docker_compose_types::{Command, Compose, Service, ServiceHook};
+
+    let yaml =
+        std::fs::read_to_string("tests/fixtures/lifecycle-hooks/docker-compose.yml").unwrap();
+    let compose: Compose = from_str(&yaml).unwrap();
+    let app = compose.services.0["app"].as_ref().unwrap();
+    let post_start = &app.post_start[0];
+    assert_eq!(
+        post_start.command,
+        Command::Simple("./scripts/warm-cache.sh".to_string())
+    );
+    assert_eq!(post_start.user.as_deref(), Some("root"));
+    assert!(post_start.privileged);
+    assert_eq!(post_start.working_dir.as_deref(), Some("/app"));
+    assert_eq!(
+        post_start.environment.normalize().get("CACHE_SIZE"),
+        Some(Some("512"))
+    );
+    assert_eq!(app.pre_stop.len(), 2);
+    assert!(matches!(&app.pre_stop[0].command, Command::Args(args) if args.len() == 3));
+    assert!(!app.pre_stop[0].privileged);
+    assert!(compose.validate().is_empty());
+
+    let round_trip: Compose = from_str(&to_string(&compose).unwrap()).unwrap();
+    assert_eq!(round_trip, compose);
+
+    let hook = |command: &str| ServiceHook {
+        command: Command::Simple(command.to_string()),
+        user: None,
+        privileged: false,
+        working_dir: None,
+        environment: Default::default(),
+    };
+    let merged = app.clone().merge(Service {
+        pre_stop: vec![hook("./scripts/notify.sh")],
+        ..Default::default()
+    });
+    assert_eq!(merged.post_start, app.post_start);
+    assert_eq!(merged.pre_stop.len(), 3);
+    assert_eq!(merged.pre_stop[2], hook("./scripts/notify.sh"));
+
+    let mut invalid = compose.clone();
+    let invalid_app = invalid.services.0.get_mut("app").unwrap().as_mut().unwrap();
+    invalid_app.post_start = vec![hook(" ")];
+    let diagnostics: Vec<_> = invalid.validate().iter().map(ToString::to_string).collect();
+    assert_eq!(
+        diagnostics,
+        ["error: services.app.post_start[0].command: command is empty"]
+    );
+}